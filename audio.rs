@@ -0,0 +1,154 @@
+use crate::speech::{self, Speech};
+use crate::stretch::time_stretch;
+use rodio::buffer::SamplesBuffer;
+use rodio::source::Zero;
+use rodio::{Decoder, OutputStream, Sink, Source};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Silence between the clips of one Play, e.g. reference and own recording.
+const PAUSE: Duration = Duration::from_millis(500);
+
+/// Something to play: a name looked up by the speech backends, or a file.
+enum Clip {
+    Named(String),
+    File(PathBuf),
+}
+
+enum Command {
+    /// Plays the clips one after the other, with a short pause between them.
+    Play(Vec<Clip>),
+    /// Playback speed for the next clips, keeping their pitch.
+    Speed(f32),
+    /// Folder with clips used in place of the built-in ones.
+    Folder(Option<PathBuf>),
+}
+
+/// Handle to the audio thread, which owns the output stream for the whole
+/// run of the app. Starting a clip stops the one still playing, and nothing
+/// waits for playback to end.
+#[derive(Clone)]
+pub struct Audio {
+    commands: Sender<Command>,
+}
+
+impl Audio {
+    pub fn start() -> Audio {
+        let (commands, receiver) = mpsc::channel();
+        thread::spawn(move || run(receiver));
+        Audio { commands }
+    }
+
+    /// Plays the recording of a name, or speaks it if there is none.
+    pub fn play(&self, name: &str) {
+        self.send(Command::Play(vec![Clip::Named(name.to_string())]));
+    }
+
+    pub fn play_file(&self, path: PathBuf) {
+        self.send(Command::Play(vec![Clip::File(path)]));
+    }
+
+    /// Plays the reference for `name`, then the learner's own recording.
+    pub fn compare(&self, name: &str, own: PathBuf) {
+        self.send(Command::Play(vec![
+            Clip::Named(name.to_string()),
+            Clip::File(own),
+        ]));
+    }
+
+    pub fn set_speed(&self, speed: f32) {
+        self.send(Command::Speed(speed));
+    }
+
+    pub fn set_folder(&self, folder: Option<PathBuf>) {
+        self.send(Command::Folder(folder));
+    }
+
+    fn send(&self, command: Command) {
+        if self.commands.send(command).is_err() {
+            println!("Audio thread is not running")
+        }
+    }
+}
+
+fn open(clip: &Clip, backends: &[Box<dyn Speech>]) -> Result<Decoder<Cursor<Vec<u8>>>, String> {
+    let (data, name) = match clip {
+        Clip::Named(name) => (
+            backends
+                .iter()
+                .find_map(|backend| backend.speak(name))
+                .ok_or(format!("No recording or speech for {}", name))?,
+            name.clone(),
+        ),
+        Clip::File(path) => (
+            fs::read(path).map_err(|e| format!("Can't open {}: {}", path.display(), e))?,
+            path.display().to_string(),
+        ),
+    };
+    Decoder::new(Cursor::new(data)).map_err(|e| format!("Can't decode {}: {}", name, e))
+}
+
+fn run(receiver: Receiver<Command>) {
+    // The stream stops playing when dropped, so it lives as long as the thread.
+    let (_stream, stream_handle) = match OutputStream::try_default() {
+        Ok(output) => output,
+        Err(e) => {
+            println!("No audio output: {}", e);
+            return;
+        }
+    };
+    let mut current: Option<Sink> = None;
+    let mut speed = 1.0;
+    let mut backends = speech::backends(None);
+
+    for command in receiver {
+        let clips = match command {
+            Command::Play(clips) => clips,
+            Command::Speed(s) => {
+                speed = s;
+                continue;
+            }
+            Command::Folder(folder) => {
+                backends = speech::backends(folder);
+                continue;
+            }
+        };
+        if let Some(sink) = current.take() {
+            sink.stop();
+        }
+
+        let sink = match Sink::try_new(&stream_handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                println!("Can't play: {}", e);
+                continue;
+            }
+        };
+        for (i, clip) in clips.iter().enumerate() {
+            let source = match open(clip, &backends) {
+                Ok(source) => source,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
+            };
+            if i > 0 {
+                sink.append(Zero::<f32>::new(1, source.sample_rate()).take_duration(PAUSE));
+            }
+            if (speed - 1.0f32).abs() < 0.01 {
+                sink.append(source);
+            } else {
+                let channels = source.channels();
+                let sample_rate = source.sample_rate();
+                let samples: Vec<f32> = source.convert_samples().collect();
+                let stretched = time_stretch(&samples, channels as usize, sample_rate, speed);
+                sink.append(SamplesBuffer::new(channels, sample_rate, stretched));
+            }
+        }
+        current = Some(sink);
+    }
+}
@@ -0,0 +1,42 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Audio formats rodio can decode.
+const FORMATS: [&str; 4] = ["mp3", "ogg", "wav", "flac"];
+
+/// Compiles every clip in `audio/` and its manifest into the binary, so audio
+/// works no matter which directory the app is started from.
+fn main() {
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("audio");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut clips: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|e| FORMATS.iter().any(|f| e == *f))
+        })
+        .collect();
+    clips.sort();
+
+    let mut code = String::from("pub static CLIPS: &[(&str, &[u8])] = &[\n");
+    for path in clips {
+        let name = path.file_name().unwrap().to_string_lossy();
+        code.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            name,
+            path.display().to_string()
+        ));
+    }
+    code.push_str("];\n");
+    code.push_str(&format!(
+        "pub static MANIFEST: &str = include_str!({:?});\n",
+        dir.join("manifest.tsv").display().to_string()
+    ));
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("clips.rs");
+    fs::write(out, code).unwrap();
+}
@@ -0,0 +1,254 @@
+use crate::thai::{ConsonantClass, MID_CLASS_MNEMONIC};
+use crate::{Context, Letter};
+use gtk::gdk::{DragAction, ModifierType};
+use gtk::prelude::*;
+use gtk::{
+    Application, ApplicationWindow, Button, DestDefaults, EventBox, Label, TargetEntry, TargetFlags,
+};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+const BINS: [ConsonantClass; 3] = [
+    ConsonantClass::Low,
+    ConsonantClass::Mid,
+    ConsonantClass::High,
+];
+
+struct ClassSort {
+    queue: Vec<(String, ConsonantClass)>,
+    pos: usize,
+    mistakes: usize,
+    errors: HashMap<String, usize>,
+    started: Instant,
+}
+
+impl ClassSort {
+    fn new(letters: &[Letter], unlocked: &[usize]) -> ClassSort {
+        let mut queue: Vec<(String, ConsonantClass)> = unlocked
+            .iter()
+            .map(|i| &letters[*i])
+            .filter_map(|l| l.class().map(|c| (l.letter.clone(), c)))
+            .collect();
+        queue.shuffle(&mut rand::thread_rng());
+
+        ClassSort {
+            queue,
+            pos: 0,
+            mistakes: 0,
+            errors: HashMap::new(),
+            started: Instant::now(),
+        }
+    }
+
+    fn current(&self) -> Option<&(String, ConsonantClass)> {
+        self.queue.get(self.pos)
+    }
+
+    /// Checks the answer for the current letter and moves on to the next one.
+    fn answer(&mut self, class: ConsonantClass) -> String {
+        let (letter, expected) = self.current().unwrap().clone();
+        self.pos += 1;
+
+        if class == expected {
+            format!("Correct: {} is {} class", letter, expected)
+        } else {
+            self.mistakes += 1;
+            *self.errors.entry(letter.clone()).or_insert(0) += 1;
+            format!("Wrong: {} is {} class, not {}", letter, expected, class)
+        }
+    }
+
+    fn summary(&self) -> String {
+        let secs = self.started.elapsed().as_secs();
+        let mut missed: Vec<(&String, &usize)> = self.errors.iter().collect();
+        missed.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let missed: Vec<String> = missed
+            .iter()
+            .map(|(l, n)| format!("{} ×{}", l, n))
+            .collect();
+
+        format!(
+            "Sorted {} consonants in {}:{:02} with {} mistakes\nMissed: {}",
+            self.queue.len(),
+            secs / 60,
+            secs % 60,
+            self.mistakes,
+            if missed.is_empty() {
+                String::from("none")
+            } else {
+                missed.join(", ")
+            }
+        )
+    }
+}
+
+fn show_current(game: &ClassSort, label_letter: &Label, label_progress: &Label) {
+    match game.current() {
+        Some((letter, _)) => {
+            label_letter.set_markup(&format!(
+                "<span font_desc='Noto Looped Thai UI Normal 60'>{}</span>",
+                letter
+            ));
+            label_progress.set_text(&format!(
+                "{} / {}   Mistakes: {}",
+                game.pos + 1,
+                game.queue.len(),
+                game.mistakes
+            ));
+        }
+        None => {
+            label_letter.set_text("");
+            label_progress.set_text(&game.summary());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct View {
+    label_letter: Label,
+    label_progress: Label,
+    label_feedback: Label,
+}
+
+fn drop_into(
+    game: &Arc<Mutex<ClassSort>>,
+    class: ConsonantClass,
+    view: &View,
+    shared_state: &Arc<Mutex<Context>>,
+) {
+    let mut g = game.lock().unwrap();
+    let (letter, expected) = match g.current() {
+        Some(current) => current.clone(),
+        None => return,
+    };
+    let mut state = shared_state.lock().unwrap();
+    state.answer(&letter, None, class == expected);
+    state.goals.study();
+    drop(state);
+    view.label_feedback.set_text(&g.answer(class));
+    show_current(&g, &view.label_letter, &view.label_progress);
+}
+
+/// A game over the letters unlocked so far.
+fn new_game(letters: &Mutex<Vec<Letter>>, shared_state: &Mutex<Context>) -> ClassSort {
+    let binding = letters.lock().unwrap();
+    let unlocked = shared_state.lock().unwrap().unlock.unlocked(&binding);
+    ClassSort::new(&binding, &unlocked)
+}
+
+pub fn build_class_sort_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Consonant classes")
+        .default_width(600)
+        .default_height(400)
+        .build();
+
+    let targets = [TargetEntry::new("text/plain", TargetFlags::SAME_APP, 0)];
+
+    let view = View {
+        label_letter: Label::new(Some("")),
+        label_progress: Label::new(Some("")),
+        label_feedback: Label::new(Some("Drag the letter into its class, or press 1, 2 or 3")),
+    };
+    let label_hint = Label::new(None);
+    let button_hint = Button::with_label("Hint");
+    let button_restart = Button::with_label("Start again");
+
+    let event_box = EventBox::new();
+    event_box.add(&view.label_letter);
+    event_box.drag_source_set(ModifierType::BUTTON1_MASK, &targets, DragAction::MOVE);
+    let letter_1 = view.label_letter.clone();
+    event_box.connect_drag_data_get(move |_, _, data, _, _| {
+        data.set_text(&letter_1.text());
+    });
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_bins = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let h_box_buttons = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+
+    let game = Arc::new(Mutex::new(new_game(&letters, &shared_state)));
+
+    for (n, class) in BINS.iter().copied().enumerate() {
+        let bin = Button::with_label(&format!("{}. {} class", n + 1, class));
+        bin.set_size_request(150, 120);
+        bin.drag_dest_set(DestDefaults::ALL, &targets, DragAction::MOVE);
+
+        let game_1 = game.clone();
+        let view_1 = view.clone();
+        let shared_state_1 = shared_state.clone();
+        bin.connect_drag_data_received(move |_, _, _, _, _, _, _| {
+            drop_into(&game_1, class, &view_1, &shared_state_1);
+        });
+        let game_2 = game.clone();
+        let view_2 = view.clone();
+        let shared_state_2 = shared_state.clone();
+        bin.connect_clicked(move |_| {
+            drop_into(&game_2, class, &view_2, &shared_state_2);
+        });
+
+        h_box_bins.pack_start(&bin, true, true, 0);
+    }
+
+    h_box_buttons.pack_start(&button_hint, true, false, 0);
+    h_box_buttons.pack_start(&button_restart, true, false, 0);
+
+    v_box.pack_start(&event_box, false, false, 0);
+    v_box.pack_start(&view.label_progress, false, false, 0);
+    v_box.pack_start(&h_box_bins, false, false, 0);
+    v_box.pack_start(&view.label_feedback, false, false, 0);
+    v_box.pack_start(&label_hint, false, false, 0);
+    v_box.pack_start(&h_box_buttons, false, false, 0);
+
+    show_current(
+        &game.lock().unwrap(),
+        &view.label_letter,
+        &view.label_progress,
+    );
+
+    let label_hint_1 = label_hint.clone();
+    button_hint.connect_clicked(move |_| {
+        label_hint_1.set_markup(&format!(
+            "Mid class: <span font_desc='Noto Looped Thai UI Normal 16'>{}</span>",
+            MID_CLASS_MNEMONIC
+        ));
+        label_hint_1.show();
+    });
+
+    let game_3 = game.clone();
+    let view_3 = view.clone();
+    let shared_state_3 = shared_state.clone();
+    button_restart.connect_clicked(move |_| {
+        *game_3.lock().unwrap() = new_game(&letters, &shared_state_3);
+        view_3.label_feedback.set_text("");
+        show_current(
+            &game_3.lock().unwrap(),
+            &view_3.label_letter,
+            &view_3.label_progress,
+        );
+    });
+
+    let game_4 = game.clone();
+    window.connect_key_press_event(move |_, event| {
+        let class = match event.keyval().to_unicode() {
+            Some('1') | Some('l') => ConsonantClass::Low,
+            Some('2') | Some('m') => ConsonantClass::Mid,
+            Some('3') | Some('h') => ConsonantClass::High,
+            _ => return gtk::glib::Propagation::Proceed,
+        };
+        drop_into(&game_4, class, &view, &shared_state);
+        gtk::glib::Propagation::Stop
+    });
+
+    window.add(&v_box);
+    window.show_all();
+    label_hint.hide();
+
+    window
+}
@@ -0,0 +1,280 @@
+use crate::{Context, Letter};
+use gtk::cairo::{Context as Cairo, Format, ImageSurface};
+use gtk::prelude::*;
+use gtk::{gdk, Application, ApplicationWindow, Button, Label, StyleContext};
+use pango::glib::markup_escape_text;
+use pango::{FontDescription, Layout};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::sync::{Arc, Mutex};
+
+/// Look-alikes that learners are known to mix up.
+#[rustfmt::skip]
+const CURATED: [(&str, &str); 13] = [
+    ("ด", "ต"), ("บ", "ป"), ("ผ", "ฝ"), ("ผ", "พ"), ("ผ", "ฟ"), ("ฝ", "พ"), ("ฝ", "ฟ"),
+    ("พ", "ฟ"), ("ถ", "ภ"), ("ช", "ซ"), ("ฎ", "ฏ"), ("ท", "ห"), ("ม", "น"),
+];
+
+const BITMAP_SIZE: i32 = 64;
+/// Letters whose bitmaps overlap at least this much count as look-alikes.
+const SIMILARITY_THRESHOLD: f64 = 0.6;
+
+/// Renders a letter with pango in the middle of a square grayscale bitmap,
+/// or None if no font has a glyph for it.
+fn render(context: &pango::Context, letter: &str) -> Option<Vec<u8>> {
+    let mut font = FontDescription::from_string("Noto Looped Thai UI");
+    font.set_absolute_size(BITMAP_SIZE as f64 * 0.7 * pango::SCALE as f64);
+    let layout = Layout::new(context);
+    layout.set_font_description(Some(&font));
+    layout.set_text(letter);
+    if layout.unknown_glyphs_count() > 0 {
+        return None;
+    }
+    let (ink, _) = layout.pixel_extents();
+
+    let mut surface = ImageSurface::create(Format::A8, BITMAP_SIZE, BITMAP_SIZE).ok()?;
+    {
+        let cr = Cairo::new(&surface).ok()?;
+        gtk::render_layout(
+            &StyleContext::new(),
+            &cr,
+            ((BITMAP_SIZE - ink.width()) / 2 - ink.x()) as f64,
+            ((BITMAP_SIZE - ink.height()) / 2 - ink.y()) as f64,
+            &layout,
+        );
+    }
+    surface.flush();
+
+    let stride = surface.stride() as usize;
+    let data = surface.data().ok()?;
+    let mut pixels = Vec::with_capacity((BITMAP_SIZE * BITMAP_SIZE) as usize);
+    for row in data.chunks(stride) {
+        pixels.extend_from_slice(&row[..BITMAP_SIZE as usize]);
+    }
+    Some(pixels)
+}
+
+/// How much two bitmaps overlap, from 0 (not at all) to 1 (identical).
+fn similarity(a: &[u8], b: &[u8]) -> f64 {
+    let (mut both, mut either) = (0u64, 0u64);
+    for (x, y) in a.iter().zip(b) {
+        both += (*x).min(*y) as u64;
+        either += (*x).max(*y) as u64;
+    }
+    if either == 0 {
+        return 0.0;
+    }
+    both as f64 / either as f64
+}
+
+/// The curated look-alikes plus every pair of consonants that render
+/// similarly enough, as indices into `letters`.
+pub fn confusable_pairs(letters: &[Letter]) -> Vec<(usize, usize)> {
+    let index_of = |s: &str| letters.iter().position(|l| l.letter == s);
+    let mut pairs: Vec<(usize, usize)> = CURATED
+        .iter()
+        .filter_map(|(a, b)| Some((index_of(a)?, index_of(b)?)))
+        .collect();
+
+    let context = match gdk::pango_context_get() {
+        Some(context) => context,
+        None => return pairs,
+    };
+    let rendered: Vec<(usize, Vec<u8>)> = letters
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.consonant)
+        .filter_map(|(i, l)| Some((i, render(&context, &l.letter)?)))
+        .filter(|(_, bitmap)| bitmap.iter().any(|p| *p > 0))
+        .collect();
+    // Letters drawn exactly alike came out as the same placeholder glyph.
+    let bitmaps: Vec<&(usize, Vec<u8>)> = rendered
+        .iter()
+        .filter(|(i, bitmap)| !rendered.iter().any(|(j, other)| i != j && bitmap == other))
+        .collect();
+    for (n, (a, bitmap_a)) in bitmaps.iter().enumerate() {
+        for (b, bitmap_b) in bitmaps[n + 1..].iter().copied() {
+            let known = pairs.contains(&(*a, *b)) || pairs.contains(&(*b, *a));
+            if !known && similarity(bitmap_a, bitmap_b) >= SIMILARITY_THRESHOLD {
+                pairs.push((*a, *b));
+            }
+        }
+    }
+    pairs
+}
+
+struct ConfusableDrill {
+    pairs: Vec<(usize, usize)>,
+    /// The pair on screen, left letter first.
+    shown: (usize, usize),
+    /// The letter asked for.
+    target: usize,
+    answered: bool,
+    correct: usize,
+    total: usize,
+}
+
+impl ConfusableDrill {
+    fn new(pairs: Vec<(usize, usize)>) -> ConfusableDrill {
+        let mut drill = ConfusableDrill {
+            pairs,
+            shown: (0, 0),
+            target: 0,
+            answered: false,
+            correct: 0,
+            total: 0,
+        };
+        drill.next();
+        drill
+    }
+
+    fn next(&mut self) {
+        let mut rng = rand::thread_rng();
+        let (a, b) = match self.pairs.choose(&mut rng) {
+            Some(pair) => *pair,
+            None => return,
+        };
+        self.shown = if rng.gen() { (a, b) } else { (b, a) };
+        self.target = if rng.gen() { a } else { b };
+        self.answered = false;
+    }
+}
+
+fn show_pair(
+    drill: &ConfusableDrill,
+    letters: &[Letter],
+    labels: &[Label; 2],
+    label_question: &Label,
+    label_feedback: &Label,
+) {
+    if drill.pairs.is_empty() {
+        label_question.set_text("No look-alikes among these letters");
+        return;
+    }
+    for (label, i) in labels.iter().zip([drill.shown.0, drill.shown.1]) {
+        label.set_markup(&format!(
+            "<span font_desc='Noto Looped Thai UI Normal 80'>{}</span>",
+            markup_escape_text(&letters[i].letter)
+        ));
+    }
+    label_question.set_markup(&format!(
+        "<span font_desc='Noto Looped Thai UI Normal 20'>Which one is {}?</span>",
+        markup_escape_text(&letters[drill.target].pronunciation)
+    ));
+    label_feedback.set_text("");
+}
+
+pub fn build_confusables_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Look-alike letters")
+        .default_width(600)
+        .default_height(450)
+        .build();
+
+    let label_question = Label::new(Some(""));
+    let label_feedback = Label::new(Some(""));
+    let label_score = Label::new(Some(""));
+    let button_next = Button::with_label("Next");
+    let labels = [Label::new(None), Label::new(None)];
+    let buttons = [Button::new(), Button::new()];
+    buttons[0].add(&labels[0]);
+    buttons[1].add(&labels[1]);
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_pair = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box_pair.pack_start(&buttons[0], true, true, 0);
+    h_box_pair.pack_start(&buttons[1], true, true, 0);
+    v_box.pack_start(&label_question, false, false, 0);
+    v_box.pack_start(&h_box_pair, true, true, 0);
+    v_box.pack_start(&label_feedback, false, false, 0);
+    v_box.pack_start(&label_score, false, false, 0);
+    v_box.pack_start(&button_next, false, false, 0);
+
+    let binding = letters.lock().unwrap();
+    let unlocked = shared_state.lock().unwrap().unlock.unlocked(&binding);
+    let mut pairs = confusable_pairs(&binding);
+    pairs.retain(|(a, b)| unlocked.contains(a) && unlocked.contains(b));
+    drop(binding);
+    if pairs.is_empty() {
+        for button in buttons.iter().chain([&button_next]) {
+            button.set_sensitive(false);
+        }
+    }
+    let drill = Arc::new(Mutex::new(ConfusableDrill::new(pairs)));
+    show_pair(
+        &drill.lock().unwrap(),
+        &letters.lock().unwrap(),
+        &labels,
+        &label_question,
+        &label_feedback,
+    );
+
+    for (side, button) in buttons.iter().enumerate() {
+        let letters_1 = letters.clone();
+        let drill_1 = drill.clone();
+        let shared_state_1 = shared_state.clone();
+        let label_feedback_1 = label_feedback.clone();
+        let label_score_1 = label_score.clone();
+        button.connect_clicked(move |_| {
+            let mut drill = drill_1.lock().unwrap();
+            if drill.answered {
+                return;
+            }
+            let binding = letters_1.lock().unwrap();
+            let chosen = if side == 0 {
+                drill.shown.0
+            } else {
+                drill.shown.1
+            };
+            let correct = chosen == drill.target;
+            let expected = &binding[drill.target];
+
+            drill.answered = true;
+            drill.total += 1;
+            if correct {
+                drill.correct += 1;
+                label_feedback_1.set_text(&format!(
+                    "Correct: {} is {}",
+                    expected.letter, expected.pronunciation
+                ));
+            } else {
+                label_feedback_1.set_text(&format!(
+                    "Wrong: that is {} ({}), {} is {}",
+                    binding[chosen].letter,
+                    binding[chosen].pronunciation,
+                    expected.pronunciation,
+                    expected.letter
+                ));
+            }
+            label_score_1.set_text(&format!("Score: {} / {}", drill.correct, drill.total));
+            let mut state = shared_state_1.lock().unwrap();
+            state.answer(&expected.letter, Some(&binding[chosen].letter), correct);
+            state.goals.study();
+        });
+    }
+
+    let letters_2 = letters.clone();
+    let label_question_2 = label_question.clone();
+    let label_feedback_2 = label_feedback.clone();
+    button_next.connect_clicked(move |_| {
+        let mut drill = drill.lock().unwrap();
+        drill.next();
+        show_pair(
+            &drill,
+            &letters_2.lock().unwrap(),
+            &labels,
+            &label_question_2,
+            &label_feedback_2,
+        );
+    });
+
+    window.add(&v_box);
+    window.show_all();
+
+    window
+}
@@ -0,0 +1,61 @@
+use crate::study_sets::load_sets;
+use crate::Letter;
+
+/// A named selection of entries from `get_letters()`, stored as indices.
+#[derive(Clone)]
+pub struct Deck {
+    pub name: String,
+    pub indices: Vec<usize>,
+}
+
+pub fn builtin_decks(letters: &[Letter]) -> Vec<Deck> {
+    let select = |f: &dyn Fn(&Letter) -> bool| -> Vec<usize> {
+        letters
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| f(l))
+            .map(|(i, _)| i)
+            .collect()
+    };
+
+    vec![
+        Deck {
+            name: String::from("All letters"),
+            indices: select(&|_| true),
+        },
+        Deck {
+            name: String::from("Consonants"),
+            indices: select(&|l| l.consonant),
+        },
+        Deck {
+            name: String::from("Vowels"),
+            indices: select(&|l| !l.consonant),
+        },
+    ]
+}
+
+/// Whether a built-in deck has this name; study sets can't take it, as
+/// decks and high scores are told apart by name.
+pub fn is_builtin(name: &str) -> bool {
+    builtin_decks(&[]).iter().any(|d| d.name == name)
+}
+
+/// The built-in decks followed by the learner's own study sets.
+pub fn all_decks(letters: &[Letter]) -> Vec<Deck> {
+    let mut decks = builtin_decks(letters);
+    for set in load_sets().into_iter().filter(|s| !is_builtin(&s.name)) {
+        let indices: Vec<usize> = letters
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| set.letters.contains(&l.letter))
+            .map(|(i, _)| i)
+            .collect();
+        if !indices.is_empty() {
+            decks.push(Deck {
+                name: set.name,
+                indices,
+            });
+        }
+    }
+    decks
+}
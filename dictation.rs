@@ -0,0 +1,324 @@
+use crate::keyboard::Keyboard;
+use crate::manifest::ClipKind;
+use crate::thai::graphemes;
+use crate::{Context, Letter};
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, Button, Entry, Label};
+use pango::glib::markup_escape_text;
+use rand::seq::SliceRandom;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Step {
+    Same,
+    /// The expected grapheme was written as a different one.
+    Wrong,
+    /// A grapheme was typed that isn't in the word.
+    Extra,
+    /// A grapheme of the word was left out.
+    Missing,
+}
+
+/// Lines the typed graphemes up with the expected ones with as few changes
+/// as possible, so one slip doesn't mark the rest of the word wrong.
+fn align(expected: &[String], typed: &[String]) -> Vec<(Step, usize, usize)> {
+    let (n, m) = (expected.len(), typed.len());
+    let mut cost = vec![vec![0; m + 1]; n + 1];
+    for (i, row) in cost.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in cost[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let change = usize::from(expected[i - 1] != typed[j - 1]);
+            cost[i][j] = (cost[i - 1][j - 1] + change)
+                .min(cost[i - 1][j] + 1)
+                .min(cost[i][j - 1] + 1);
+        }
+    }
+
+    let mut steps = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let change = usize::from(expected[i - 1] != typed[j - 1]);
+            if cost[i][j] == cost[i - 1][j - 1] + change {
+                let step = if change == 0 { Step::Same } else { Step::Wrong };
+                steps.push((step, i - 1, j - 1));
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && cost[i][j] == cost[i - 1][j] + 1 {
+            steps.push((Step::Missing, i - 1, j));
+            i -= 1;
+        } else {
+            steps.push((Step::Extra, i, j - 1));
+            j -= 1;
+        }
+    }
+    steps.reverse();
+    steps
+}
+
+/// The typed word with wrong and extra graphemes in red, and the word itself
+/// with the graphemes that were missed or misspelled in green.
+fn marked_up(expected: &str, typed: &str) -> (String, String) {
+    let expected = graphemes(expected);
+    let typed = graphemes(typed);
+    let mut typed_markup = String::new();
+    let mut expected_markup = String::new();
+
+    for (step, i, j) in align(&expected, &typed) {
+        match step {
+            Step::Same => {
+                typed_markup.push_str(&markup_escape_text(&typed[j]));
+                expected_markup.push_str(&markup_escape_text(&expected[i]));
+            }
+            Step::Wrong => {
+                typed_markup.push_str(&format!(
+                    "<span foreground='#c0392b'>{}</span>",
+                    markup_escape_text(&typed[j])
+                ));
+                expected_markup.push_str(&format!(
+                    "<span foreground='#27ae60' weight='bold'>{}</span>",
+                    markup_escape_text(&expected[i])
+                ));
+            }
+            Step::Extra => {
+                typed_markup.push_str(&format!(
+                    "<span foreground='#c0392b' strikethrough='true'>{}</span>",
+                    markup_escape_text(&typed[j])
+                ));
+            }
+            Step::Missing => {
+                typed_markup.push_str("<span foreground='#c0392b'>_</span>");
+                expected_markup.push_str(&format!(
+                    "<span foreground='#27ae60' weight='bold' underline='single'>{}</span>",
+                    markup_escape_text(&expected[i])
+                ));
+            }
+        }
+    }
+    (typed_markup, expected_markup)
+}
+
+fn play_word(letters: &Arc<Mutex<Vec<Letter>>>, shared_state: &Arc<Mutex<Context>>, index: usize) {
+    let entry = letters.lock().unwrap()[index].letter.clone();
+    let state = shared_state.lock().unwrap();
+    state
+        .audio
+        .play(&state.manifest.clip_of_kind(&entry, ClipKind::Example));
+}
+
+struct Dictation {
+    /// Entries with an example word and a recording of it.
+    items: Vec<usize>,
+    current: usize,
+    checked: bool,
+    correct: usize,
+    total: usize,
+}
+
+impl Dictation {
+    fn new(letters: &[Letter], unlocked: &[usize]) -> Dictation {
+        let items: Vec<usize> = unlocked
+            .iter()
+            .copied()
+            .filter(|i| letters[*i].consonant && letters[*i].example_word().is_some())
+            .collect();
+        let mut dictation = Dictation {
+            items,
+            current: 0,
+            checked: false,
+            correct: 0,
+            total: 0,
+        };
+        dictation.next();
+        dictation
+    }
+
+    fn next(&mut self) {
+        self.current = *self.items.choose(&mut rand::thread_rng()).unwrap();
+        self.checked = false;
+    }
+}
+
+pub fn build_dictation_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Spelling dictation")
+        .default_width(700)
+        .default_height(500)
+        .build();
+
+    let label_prompt = Label::new(Some("Listen and type the word in Thai"));
+    let button_play = Button::with_label("Play again");
+    let entry = Entry::new();
+    let button_check = Button::with_label("Check");
+    let button_next = Button::with_label("Next word");
+    let label_typed = Label::new(None);
+    let label_answer = Label::new(None);
+    let label_score = Label::new(None);
+    let keyboard = Keyboard::new();
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_buttons = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box_buttons.pack_start(&button_play, true, false, 0);
+    h_box_buttons.pack_start(&button_check, true, false, 0);
+    h_box_buttons.pack_start(&button_next, true, false, 0);
+    v_box.pack_start(&label_prompt, false, false, 0);
+    v_box.pack_start(&entry, false, false, 0);
+    v_box.pack_start(&h_box_buttons, false, false, 0);
+    v_box.pack_start(&label_typed, false, false, 0);
+    v_box.pack_start(&label_answer, false, false, 0);
+    v_box.pack_start(&label_score, false, false, 0);
+    v_box.pack_start(&keyboard.widget, false, false, 0);
+
+    let binding = letters.lock().unwrap();
+    let unlocked = shared_state.lock().unwrap().unlock.unlocked(&binding);
+    let dictation = Arc::new(Mutex::new(Dictation::new(&binding, &unlocked)));
+    drop(binding);
+    play_word(&letters, &shared_state, dictation.lock().unwrap().current);
+
+    let letters_1 = letters.clone();
+    let shared_state_1 = shared_state.clone();
+    let dictation_1 = dictation.clone();
+    button_play.connect_clicked(move |_| {
+        play_word(
+            &letters_1,
+            &shared_state_1,
+            dictation_1.lock().unwrap().current,
+        );
+    });
+
+    let letters_2 = letters.clone();
+    let shared_state_2 = shared_state.clone();
+    let dictation_2 = dictation.clone();
+    let entry_2 = entry.clone();
+    let label_typed_2 = label_typed.clone();
+    let label_answer_2 = label_answer.clone();
+    button_check.connect_clicked(move |_| {
+        let mut d = dictation_2.lock().unwrap();
+        let typed = entry_2.text().trim().to_string();
+        if d.checked || typed.is_empty() {
+            return;
+        }
+        let binding = letters_2.lock().unwrap();
+        let l = &binding[d.current];
+        let word = l.example_word().unwrap();
+        let correct = typed == word;
+
+        let (typed_markup, word_markup) = marked_up(word, &typed);
+        label_typed_2.set_markup(&format!(
+            "<span font_desc='Noto Looped Thai UI Normal 30'>{}</span>  {}",
+            typed_markup,
+            if correct { "Correct" } else { "You typed" }
+        ));
+        label_answer_2.set_markup(&format!(
+            "<span font_desc='Noto Looped Thai UI Normal 30'>{}</span>  {}",
+            word_markup,
+            markup_escape_text(l.example_meaning.as_deref().unwrap_or(""))
+        ));
+
+        d.checked = true;
+        d.total += 1;
+        if correct {
+            d.correct += 1;
+        }
+        label_score.set_text(&format!("Score: {} / {}", d.correct, d.total));
+        let mut state = shared_state_2.lock().unwrap();
+        state.answer(word, Some(&typed), correct);
+        state.goals.study();
+    });
+
+    let button_check_3 = button_check.clone();
+    entry.connect_activate(move |_| button_check_3.clicked());
+
+    let entry_4 = entry.clone();
+    keyboard.connect_key(move |c| {
+        let mut position = entry_4.position();
+        entry_4.insert_text(&c.to_string(), &mut position);
+        entry_4.set_position(position);
+    });
+
+    let entry_5 = entry.clone();
+    button_next.connect_clicked(move |_| {
+        dictation.lock().unwrap().next();
+        entry_5.set_text("");
+        label_typed.set_text("");
+        label_answer.set_text("");
+        entry_5.grab_focus();
+        play_word(&letters, &shared_state, dictation.lock().unwrap().current);
+    });
+
+    window.add(&v_box);
+    window.show_all();
+    entry.grab_focus();
+
+    window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(expected: &str, typed: &str) -> Vec<(Step, usize, usize)> {
+        align(&graphemes(expected), &graphemes(typed))
+    }
+
+    #[test]
+    fn marks_stay_on_their_letter() {
+        assert_eq!(graphemes("ไก่"), ["ไ", "ก่"]);
+        assert_eq!(graphemes("เกี้ย"), ["เ", "กี้", "ย"]);
+    }
+
+    #[test]
+    fn right_word() {
+        assert_eq!(
+            steps("ควาย", "ควาย"),
+            [
+                (Step::Same, 0, 0),
+                (Step::Same, 1, 1),
+                (Step::Same, 2, 2),
+                (Step::Same, 3, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn forgotten_tone_mark_is_one_wrong_grapheme() {
+        assert_eq!(steps("ไก่", "ไก"), [(Step::Same, 0, 0), (Step::Wrong, 1, 1)]);
+    }
+
+    #[test]
+    fn one_slip_leaves_the_rest_right() {
+        assert_eq!(
+            steps("ควาย", "คาย"),
+            [
+                (Step::Same, 0, 0),
+                (Step::Missing, 1, 1),
+                (Step::Same, 2, 1),
+                (Step::Same, 3, 2)
+            ]
+        );
+        assert_eq!(steps("งู", "งงู"), [(Step::Extra, 0, 0), (Step::Same, 0, 1)]);
+    }
+
+    #[test]
+    fn marked_up_colours_only_the_mistakes() {
+        let (typed, expected) = marked_up("ไก่", "ไก");
+        assert_eq!(typed, "ไ<span foreground='#c0392b'>ก</span>");
+        assert_eq!(
+            expected,
+            "ไ<span foreground='#27ae60' weight='bold'>ก่</span>"
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use gtk::glib;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Changes that views of the shared state follow.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Another letter or syllable is on screen.
+    CardShown,
+    /// A card was answered, here or in a drill window.
+    Studied,
+    /// The learner saved a recording.
+    Recorded,
+    /// The audio folder changed, and with it the clips.
+    AudioChanged,
+}
+
+type Subscriber = Rc<dyn Fn(Event)>;
+
+thread_local! {
+    static SUBSCRIBERS: RefCell<Vec<Subscriber>> = RefCell::new(Vec::new());
+}
+
+/// Calls `f` with every event from now on, on the GTK thread.
+pub fn subscribe(f: impl Fn(Event) + 'static) {
+    SUBSCRIBERS.with(|s| s.borrow_mut().push(Rc::new(f)));
+}
+
+/// Tells the subscribers once the handler emitting it has returned, as it
+/// may still hold the shared state they are going to lock.
+pub fn emit(event: Event) {
+    glib::idle_add_local_once(move || {
+        let subscribers = SUBSCRIBERS.with(|s| s.borrow().clone());
+        for subscriber in subscribers {
+            subscriber(event);
+        }
+    });
+}
@@ -0,0 +1,539 @@
+use crate::deck::{all_decks, Deck};
+use crate::storage;
+use crate::thai::{normalize, ConsonantClass};
+use crate::{Context, Letter};
+use gtk::prelude::*;
+use gtk::{
+    Application, ApplicationWindow, Button, ButtonsType, ComboBoxText, DialogFlags, Entry,
+    FileChooserAction, FileChooserDialog, Label, MessageDialog, MessageType, ResponseType,
+    SpinButton,
+};
+use pango::glib::markup_escape_text;
+use rand::seq::SliceRandom;
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+const CHOICES: usize = 4;
+
+/// Takes an answer and shows the next question. It is filled in after the
+/// question view is built, as the view's choice buttons call it.
+type AnswerHandler = Rc<RefCell<Option<Rc<dyn Fn(String)>>>>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Reading,
+    Recognition,
+    Class,
+}
+
+const SECTIONS: [Section; 3] = [Section::Reading, Section::Recognition, Section::Class];
+
+impl Section {
+    fn title(&self) -> &'static str {
+        match self {
+            Section::Reading => "Reading: romanization of a letter",
+            Section::Recognition => "Recognition: letter from its name",
+            Section::Class => "Consonant classes",
+        }
+    }
+}
+
+struct Question {
+    section: Section,
+    letter: String,
+    prompt: String,
+    expected: String,
+    /// Answers to pick from; empty when the answer is typed.
+    choices: Vec<String>,
+}
+
+struct Answer {
+    section: Section,
+    letter: String,
+    prompt: String,
+    expected: String,
+    given: String,
+    correct: bool,
+}
+
+struct Exam {
+    deck: String,
+    date: String,
+    questions: Vec<Question>,
+    answers: Vec<Answer>,
+}
+
+fn question(section: Section, l: &Letter, deck: &[&Letter]) -> Question {
+    match (section, l.class()) {
+        (Section::Recognition, _) => {
+            let mut choices = vec![l.letter.clone()];
+            // Several vowels share a name, so those can't be wrong choices.
+            let mut others: Vec<&&Letter> = deck
+                .iter()
+                .filter(|o| o.letter != l.letter && o.pronunciation != l.pronunciation)
+                .collect();
+            others.shuffle(&mut rand::thread_rng());
+            choices.extend(others.iter().take(CHOICES - 1).map(|o| o.letter.clone()));
+            choices.shuffle(&mut rand::thread_rng());
+            Question {
+                section,
+                letter: l.letter.clone(),
+                prompt: format!("Which letter is {}?", l.pronunciation),
+                expected: l.letter.clone(),
+                choices,
+            }
+        }
+        (Section::Class, Some(class)) => Question {
+            section,
+            letter: l.letter.clone(),
+            prompt: format!("Which class is {} in?", l.letter),
+            expected: class.to_string(),
+            choices: [
+                ConsonantClass::Low,
+                ConsonantClass::Mid,
+                ConsonantClass::High,
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+        },
+        // Vowels have no class, so they are asked as reading questions.
+        _ => Question {
+            section: Section::Reading,
+            letter: l.letter.clone(),
+            prompt: format!("Type the romanization of {}", l.letter),
+            expected: l.english_letter.clone(),
+            choices: Vec::new(),
+        },
+    }
+}
+
+impl Exam {
+    fn new(deck: &Deck, length: usize, letters: &[Letter]) -> Exam {
+        let cards: Vec<&Letter> = deck.indices.iter().map(|i| &letters[*i]).collect();
+        let mut order: Vec<&Letter> = Vec::new();
+        while !cards.is_empty() && order.len() < length {
+            let mut round = cards.clone();
+            round.shuffle(&mut rand::thread_rng());
+            order.extend(round);
+        }
+        order.truncate(length);
+
+        let mut questions: Vec<Question> = order
+            .iter()
+            .enumerate()
+            .map(|(n, l)| question(SECTIONS[n % SECTIONS.len()], l, &cards))
+            .collect();
+        questions.sort_by_key(|q| SECTIONS.iter().position(|s| *s == q.section));
+
+        Exam {
+            deck: deck.name.clone(),
+            date: storage::today(),
+            questions,
+            answers: Vec::new(),
+        }
+    }
+
+    fn current(&self) -> Option<&Question> {
+        self.questions.get(self.answers.len())
+    }
+
+    fn answer(&mut self, given: &str) -> Option<&Answer> {
+        let q = self.current()?;
+        let correct = match q.section {
+            Section::Reading => normalize(given) == normalize(&q.expected),
+            _ => given == q.expected,
+        };
+        self.answers.push(Answer {
+            section: q.section,
+            letter: q.letter.clone(),
+            prompt: q.prompt.clone(),
+            expected: q.expected.clone(),
+            given: given.to_string(),
+            correct,
+        });
+        self.answers.last()
+    }
+
+    /// (section, correct, asked) for every section with questions.
+    fn section_scores(&self) -> Vec<(Section, usize, usize)> {
+        SECTIONS
+            .iter()
+            .map(|s| {
+                let answers = self.answers.iter().filter(|a| a.section == *s);
+                let correct = answers.clone().filter(|a| a.correct).count();
+                (*s, correct, answers.count())
+            })
+            .filter(|(_, _, asked)| *asked > 0)
+            .collect()
+    }
+
+    fn correct(&self) -> usize {
+        self.answers.iter().filter(|a| a.correct).count()
+    }
+
+    fn grade(&self) -> &'static str {
+        match (self.correct() * 100)
+            .checked_div(self.answers.len())
+            .unwrap_or(0)
+        {
+            90.. => "A",
+            80..=89 => "B",
+            70..=79 => "C",
+            60..=69 => "D",
+            _ => "F",
+        }
+    }
+
+    fn report_text(&self) -> String {
+        let mut txt = format!(
+            "Deck: {}\nDate: {}\nScore: {} / {}   Grade: {}\n\n",
+            self.deck,
+            self.date,
+            self.correct(),
+            self.answers.len(),
+            self.grade()
+        );
+        for (section, correct, asked) in self.section_scores() {
+            txt.push_str(&format!("{}: {} / {}\n", section.title(), correct, asked));
+        }
+        txt.push_str("\nWrong answers:\n");
+        if self.correct() == self.answers.len() {
+            txt.push_str("  none\n");
+        }
+        for a in self.answers.iter().filter(|a| !a.correct) {
+            txt.push_str(&format!(
+                "  {}  answered: {}  correct: {}\n",
+                a.prompt,
+                if a.given.is_empty() { "-" } else { &a.given },
+                a.expected
+            ));
+        }
+        txt
+    }
+
+    fn report_html(&self) -> String {
+        let esc = |s: &str| markup_escape_text(s).to_string();
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Thai exam: {}</title>\n</head>\n<body>\n\
+             <h1>Thai exam: {}</h1>\n<p>Date: {}<br>Score: {} / {}<br>Grade: {}</p>\n",
+            esc(&self.deck),
+            esc(&self.deck),
+            esc(&self.date),
+            self.correct(),
+            self.answers.len(),
+            self.grade()
+        );
+        html.push_str("<h2>Sections</h2>\n<table>\n");
+        for (section, correct, asked) in self.section_scores() {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{} / {}</td></tr>\n",
+                esc(section.title()),
+                correct,
+                asked
+            ));
+        }
+        html.push_str("</table>\n<h2>Wrong answers</h2>\n<table>\n");
+        html.push_str("<tr><th>Question</th><th>Answered</th><th>Correct</th></tr>\n");
+        for a in self.answers.iter().filter(|a| !a.correct) {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                esc(&a.prompt),
+                esc(&a.given),
+                esc(&a.expected)
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    fn report_json(&self) -> String {
+        let sections: Vec<String> = self
+            .section_scores()
+            .iter()
+            .map(|(section, correct, asked)| {
+                format!(
+                    "    {{\"section\": {}, \"correct\": {}, \"asked\": {}}}",
+                    json_string(section.title()),
+                    correct,
+                    asked
+                )
+            })
+            .collect();
+        let answers: Vec<String> = self
+            .answers
+            .iter()
+            .map(|a| {
+                format!(
+                    "    {{\"section\": {}, \"question\": {}, \"answered\": {}, \"expected\": {}, \"correct\": {}}}",
+                    json_string(a.section.title()),
+                    json_string(&a.prompt),
+                    json_string(&a.given),
+                    json_string(&a.expected),
+                    a.correct
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"deck\": {},\n  \"date\": {},\n  \"correct\": {},\n  \"asked\": {},\n  \"grade\": {},\n  \"sections\": [\n{}\n  ],\n  \"answers\": [\n{}\n  ]\n}}\n",
+            json_string(&self.deck),
+            json_string(&self.date),
+            self.correct(),
+            self.answers.len(),
+            json_string(self.grade()),
+            sections.join(",\n"),
+            answers.join(",\n")
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn save_report(window: &ApplicationWindow, exam: &Rc<RefCell<Option<Exam>>>, html: bool) {
+    let dialog = FileChooserDialog::with_buttons(
+        Some("Save exam report"),
+        Some(window),
+        FileChooserAction::Save,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Save", ResponseType::Accept),
+        ],
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    if let Some(e) = exam.borrow().as_ref() {
+        let extension = if html { "html" } else { "json" };
+        dialog.set_current_name(&format!("exam-{}.{}", e.date, extension));
+    }
+
+    let exam = exam.clone();
+    let window = window.clone();
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            if let (Some(path), Some(e)) = (dialog.filename(), exam.borrow().as_ref()) {
+                let content = if html {
+                    e.report_html()
+                } else {
+                    e.report_json()
+                };
+                write_report(&window, &path, &content);
+            }
+        }
+        dialog.close();
+    });
+    dialog.show_all();
+}
+
+fn write_report(window: &ApplicationWindow, path: &Path, content: &str) {
+    if let Err(e) = fs::write(path, content) {
+        let dialog = MessageDialog::new(
+            Some(window),
+            DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+            MessageType::Error,
+            ButtonsType::Close,
+            &format!("Can't save exam report to {}: {}", path.display(), e),
+        );
+        dialog.connect_response(|dialog, _| dialog.close());
+        dialog.show_all();
+    }
+}
+
+pub fn build_exam_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Exam")
+        .default_width(600)
+        .default_height(450)
+        .build();
+
+    let decks = all_decks(&letters.lock().unwrap());
+    let combo_deck = ComboBoxText::new();
+    for deck in &decks {
+        combo_deck.append_text(&deck.name);
+    }
+    combo_deck.set_active(Some(0));
+    let spin_length = SpinButton::with_range(5.0, 100.0, 5.0);
+    spin_length.set_value(20.0);
+    let button_start = Button::with_label("Start exam");
+
+    let label_progress = Label::new(None);
+    let label_prompt = Label::new(None);
+    let entry = Entry::new();
+    let h_box_choices = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let label_report = Label::new(None);
+    let button_html = Button::with_label("Save as HTML…");
+    let button_json = Button::with_label("Save as JSON…");
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_options = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let h_box_save = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box_options.pack_start(&combo_deck, true, true, 0);
+    h_box_options.pack_start(&spin_length, false, false, 0);
+    h_box_options.pack_start(&button_start, false, false, 0);
+    h_box_save.pack_start(&button_html, true, false, 0);
+    h_box_save.pack_start(&button_json, true, false, 0);
+    v_box.pack_start(&h_box_options, false, false, 0);
+    v_box.pack_start(&label_progress, false, false, 0);
+    v_box.pack_start(&label_prompt, false, false, 0);
+    v_box.pack_start(&entry, false, false, 0);
+    v_box.pack_start(&h_box_choices, false, false, 0);
+    v_box.pack_start(&label_report, false, false, 0);
+    v_box.pack_start(&h_box_save, false, false, 0);
+
+    let exam: Rc<RefCell<Option<Exam>>> = Rc::new(RefCell::new(None));
+
+    // Shows the current question, or the report once all are answered.
+    // Answers are taken without feedback until the end.
+    let show_question: Rc<dyn Fn()> = {
+        let exam = exam.clone();
+        let entry = entry.clone();
+        let shared_state = shared_state.clone();
+        let h_box_choices = h_box_choices.clone();
+        let h_box_options = h_box_options.clone();
+        let h_box_save = h_box_save.clone();
+        let label_progress = label_progress.clone();
+        let label_prompt = label_prompt.clone();
+        let label_report = label_report.clone();
+        let answer_with: AnswerHandler = Rc::new(RefCell::new(None));
+        let answer_with_1 = answer_with.clone();
+        let exam_1 = exam.clone();
+        let entry_3 = entry.clone();
+        let show: Rc<dyn Fn()> = Rc::new(move || {
+            for child in h_box_choices.children() {
+                h_box_choices.remove(&child);
+            }
+            let binding = exam.borrow();
+            let e = binding.as_ref().unwrap();
+            match e.current() {
+                Some(q) => {
+                    label_progress.set_text(&format!(
+                        "{} — question {} of {}",
+                        q.section.title(),
+                        e.answers.len() + 1,
+                        e.questions.len()
+                    ));
+                    label_prompt.set_markup(&format!(
+                        "<span font_desc='Noto Looped Thai UI Normal 24'>{}</span>",
+                        markup_escape_text(&q.prompt)
+                    ));
+                    entry.set_text("");
+                    entry.set_visible(q.choices.is_empty());
+                    entry.grab_focus();
+                    for choice in &q.choices {
+                        let label = Label::new(None);
+                        label.set_markup(&format!(
+                            "<span font_desc='Noto Looped Thai UI Normal 24'>{}</span>",
+                            markup_escape_text(choice)
+                        ));
+                        let button = Button::new();
+                        button.add(&label);
+                        let choice = choice.clone();
+                        let answer_with_2 = answer_with_1.clone();
+                        button.connect_clicked(move |_| {
+                            let answer = answer_with_2.borrow().clone();
+                            if let Some(answer) = answer {
+                                answer(choice.clone());
+                            }
+                        });
+                        h_box_choices.pack_start(&button, true, false, 0);
+                    }
+                    h_box_choices.show_all();
+                }
+                None => {
+                    label_progress.set_text("Exam finished");
+                    label_prompt.set_text("");
+                    entry.hide();
+                    label_report.set_text(&e.report_text());
+                    h_box_save.show();
+                    h_box_options.set_sensitive(true);
+                }
+            }
+        });
+
+        let show_1 = Rc::downgrade(&show);
+        *answer_with.borrow_mut() = Some(Rc::new(move |given: String| {
+            if let Some(a) = exam_1.borrow_mut().as_mut().and_then(|e| e.answer(&given)) {
+                // Only a wrong letter picked from the choices is a confusion.
+                let given = Some(a.given.as_str()).filter(|_| a.section == Section::Recognition);
+                let mut state = shared_state.lock().unwrap();
+                state.answer(&a.letter, given, a.correct);
+                state.goals.study();
+            }
+            if let Some(show) = show_1.upgrade() {
+                show();
+            }
+        }));
+
+        let answer_with_3 = answer_with.clone();
+        entry_3.connect_activate(move |entry| {
+            let answer = answer_with_3.borrow().clone();
+            if let Some(answer) = answer {
+                answer(entry.text().trim().to_string());
+            }
+        });
+        show
+    };
+
+    let exam_1 = exam.clone();
+    let h_box_save_1 = h_box_save.clone();
+    let label_report_1 = label_report.clone();
+    button_start.connect_clicked(move |_| {
+        let mut deck = decks[combo_deck.active().unwrap() as usize].clone();
+        let binding = letters.lock().unwrap();
+        deck.indices = shared_state
+            .lock()
+            .unwrap()
+            .unlock
+            .unlocked_of(&binding, &deck.indices);
+        drop(binding);
+        if deck.indices.is_empty() {
+            label_report_1.set_text("This deck has no letters to ask");
+            return;
+        }
+        let length = spin_length.value_as_int() as usize;
+        *exam_1.borrow_mut() = Some(Exam::new(&deck, length, &letters.lock().unwrap()));
+        h_box_options.set_sensitive(false);
+        h_box_save_1.hide();
+        if length > deck.indices.len() {
+            label_report_1.set_text(&format!(
+                "The deck has {} cards, so some are asked more than once",
+                deck.indices.len()
+            ));
+        } else {
+            label_report_1.set_text("");
+        }
+        show_question();
+    });
+
+    let window_2 = window.clone();
+    let exam_2 = exam.clone();
+    button_html.connect_clicked(move |_| save_report(&window_2, &exam_2, true));
+    let window_3 = window.clone();
+    button_json.connect_clicked(move |_| save_report(&window_3, &exam, false));
+
+    window.add(&v_box);
+    window.show_all();
+    entry.hide();
+    h_box_save.hide();
+
+    window
+}
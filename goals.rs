@@ -0,0 +1,361 @@
+use crate::events::{self, Event};
+use crate::storage;
+use crate::Context;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::{ComboBoxText, Label, SpinButton};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const GOALS_FILE: &str = "goals.tsv";
+/// Pauses longer than this between two cards don't count as study time.
+const MAX_PAUSE: Duration = Duration::from_secs(60);
+/// A streak freeze is earned back every this many days of streak.
+const FREEZE_EVERY: u32 = 7;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GoalKind {
+    Cards,
+    Minutes,
+}
+
+impl GoalKind {
+    fn id(&self) -> &'static str {
+        match self {
+            GoalKind::Cards => "cards",
+            GoalKind::Minutes => "minutes",
+        }
+    }
+
+    fn from_id(id: &str) -> GoalKind {
+        match id {
+            "minutes" => GoalKind::Minutes,
+            _ => GoalKind::Cards,
+        }
+    }
+}
+
+/// A daily goal and the streak of days it was met. A missed day breaks the
+/// streak unless a freeze is left to cover it.
+#[derive(Clone)]
+pub struct Goals {
+    pub kind: GoalKind,
+    pub target: u32,
+    pub max_freezes: u32,
+    pub freezes: u32,
+    pub streak: u32,
+    /// Last day the streak was kept, by meeting the goal or by a freeze.
+    last_kept: Option<String>,
+    day: String,
+    cards: u32,
+    seconds: u64,
+    last_activity: Option<Instant>,
+}
+
+/// Noon of a YYYY-MM-DD day, so a daylight saving change can't shift it.
+fn parse_day(day: &str) -> Option<glib::DateTime> {
+    let mut parts = day.split('-').map(|p| p.parse::<i32>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    glib::DateTime::from_local(y, m, d, 12, 0, 0.0).ok()
+}
+
+/// Whole days from `from` to `to`, both YYYY-MM-DD.
+fn days_between(from: &str, to: &str) -> Option<i64> {
+    Some(parse_day(to)?.difference(&parse_day(from)?).as_days())
+}
+
+fn day_before(day: &str) -> Option<String> {
+    parse_day(day)?
+        .add_days(-1)
+        .ok()?
+        .format("%Y-%m-%d")
+        .ok()
+        .map(|d| d.to_string())
+}
+
+impl Goals {
+    pub fn load() -> Goals {
+        let mut goals = Goals {
+            kind: GoalKind::Cards,
+            target: 20,
+            max_freezes: 2,
+            freezes: 2,
+            streak: 0,
+            last_kept: None,
+            day: storage::today(),
+            cards: 0,
+            seconds: 0,
+            last_activity: None,
+        };
+        for record in storage::read_records(&storage::data_file(GOALS_FILE)) {
+            match record.as_slice() {
+                [kind, goal, target, max_freezes] if kind == "goal" => {
+                    goals.kind = GoalKind::from_id(goal);
+                    goals.target = target.parse().unwrap_or(goals.target).max(1);
+                    goals.max_freezes = max_freezes.parse().unwrap_or(goals.max_freezes);
+                }
+                [kind, streak, last_kept, freezes] if kind == "streak" => {
+                    goals.streak = streak.parse().unwrap_or(0);
+                    goals.last_kept = Some(last_kept.clone()).filter(|d| !d.is_empty());
+                    goals.freezes = freezes.parse().unwrap_or(0);
+                }
+                [kind, day, cards, seconds] if kind == "day" => {
+                    goals.day = day.clone();
+                    goals.cards = cards.parse().unwrap_or(0);
+                    goals.seconds = seconds.parse().unwrap_or(0);
+                }
+                _ => (),
+            }
+        }
+        goals.freezes = goals.freezes.min(goals.max_freezes);
+        goals.roll_over();
+        goals
+    }
+
+    pub fn save(&self) {
+        let records = vec![
+            vec![
+                String::from("goal"),
+                self.kind.id().to_string(),
+                self.target.to_string(),
+                self.max_freezes.to_string(),
+            ],
+            vec![
+                String::from("streak"),
+                self.streak.to_string(),
+                self.last_kept.clone().unwrap_or_default(),
+                self.freezes.to_string(),
+            ],
+            vec![
+                String::from("day"),
+                self.day.clone(),
+                self.cards.to_string(),
+                self.seconds.to_string(),
+            ],
+        ];
+        storage::write_records(&storage::data_file(GOALS_FILE), &records);
+    }
+
+    /// Starts a new day if the date changed, checking whether the streak
+    /// survived the days in between.
+    pub fn roll_over(&mut self) {
+        if self.roll_over_to(&storage::today()) {
+            self.save();
+        }
+    }
+
+    /// Returns whether the streak or the freezes changed.
+    fn roll_over_to(&mut self, today: &str) -> bool {
+        if self.day != today {
+            self.day = today.to_string();
+            self.cards = 0;
+            self.seconds = 0;
+            self.last_activity = None;
+        }
+
+        let missed = match &self.last_kept {
+            Some(kept) => days_between(kept, today).unwrap_or(0) - 1,
+            None => 0,
+        };
+        if missed <= 0 {
+            return false;
+        }
+        if missed as u32 <= self.freezes {
+            self.freezes -= missed as u32;
+            self.last_kept = day_before(today);
+        } else {
+            self.streak = 0;
+            self.last_kept = None;
+        }
+        true
+    }
+
+    fn progress(&self) -> u32 {
+        match self.kind {
+            GoalKind::Cards => self.cards,
+            GoalKind::Minutes => (self.seconds / 60) as u32,
+        }
+    }
+
+    pub fn met_today(&self) -> bool {
+        self.progress() >= self.target
+    }
+
+    /// A card was reviewed or answered. The time since the previous one
+    /// counts as study time, unless it was a long pause.
+    pub fn study(&mut self) {
+        self.roll_over();
+        self.count_card(Instant::now());
+        self.save();
+    }
+
+    fn count_card(&mut self, now: Instant) {
+        self.cards += 1;
+        if let Some(last) = self.last_activity {
+            self.seconds += now.duration_since(last).min(MAX_PAUSE).as_secs();
+        }
+        self.last_activity = Some(now);
+
+        if self.met_today() && self.last_kept.as_deref() != Some(self.day.as_str()) {
+            self.streak += 1;
+            self.last_kept = Some(self.day.clone());
+            if self.streak.is_multiple_of(FREEZE_EVERY) && self.freezes < self.max_freezes {
+                self.freezes += 1;
+            }
+        }
+    }
+
+    pub fn status(&self) -> String {
+        let unit = match self.kind {
+            GoalKind::Cards => "cards",
+            GoalKind::Minutes => "minutes",
+        };
+        format!(
+            "Today: {} / {} {}{}    Streak: {} day{}    Freezes left: {}",
+            self.progress(),
+            self.target,
+            unit,
+            if self.met_today() { " ✓" } else { "" },
+            self.streak,
+            if self.streak == 1 { "" } else { "s" },
+            self.freezes
+        )
+    }
+}
+
+/// The status area of the main window: today's progress, the streak and the
+/// goal settings.
+pub fn build_goal_status(shared_state: Arc<Mutex<Context>>) -> gtk::Box {
+    let goals = shared_state.lock().unwrap().goals.clone();
+
+    let label_status = Label::new(Some(&goals.status()));
+    let combo_kind = ComboBoxText::new();
+    combo_kind.append(Some("cards"), "Cards per day");
+    combo_kind.append(Some("minutes"), "Minutes per day");
+    combo_kind.set_active_id(Some(goals.kind.id()));
+    let spin_target = SpinButton::with_range(1.0, 500.0, 1.0);
+    spin_target.set_value(goals.target as f64);
+    let label_freezes = Label::new(Some("Freezes"));
+    let spin_freezes = SpinButton::with_range(0.0, 7.0, 1.0);
+    spin_freezes.set_value(goals.max_freezes as f64);
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let h_box_settings = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box_settings.pack_start(&combo_kind, false, false, 0);
+    h_box_settings.pack_start(&spin_target, false, false, 0);
+    h_box_settings.pack_start(&label_freezes, false, false, 0);
+    h_box_settings.pack_start(&spin_freezes, false, false, 0);
+    v_box.pack_start(&label_status, false, false, 0);
+    v_box.pack_start(&h_box_settings, false, false, 0);
+
+    let shared_state_1 = shared_state.clone();
+    let label_status_1 = label_status.clone();
+    combo_kind.connect_changed(move |combo| {
+        let mut state = shared_state_1.lock().unwrap();
+        state.goals.kind = GoalKind::from_id(&combo.active_id().unwrap_or_default());
+        state.goals.save();
+        label_status_1.set_text(&state.goals.status());
+    });
+
+    let shared_state_2 = shared_state.clone();
+    let label_status_2 = label_status.clone();
+    spin_target.connect_value_changed(move |spin| {
+        let mut state = shared_state_2.lock().unwrap();
+        state.goals.target = spin.value_as_int() as u32;
+        state.goals.save();
+        label_status_2.set_text(&state.goals.status());
+    });
+
+    let shared_state_3 = shared_state.clone();
+    let label_status_3 = label_status.clone();
+    spin_freezes.connect_value_changed(move |spin| {
+        let mut state = shared_state_3.lock().unwrap();
+        state.goals.max_freezes = spin.value_as_int() as u32;
+        state.goals.freezes = state.goals.freezes.min(state.goals.max_freezes);
+        state.goals.save();
+        label_status_3.set_text(&state.goals.status());
+    });
+
+    // Drills in other windows count towards the goal too.
+    events::subscribe(move |event| {
+        if matches!(event, Event::CardShown | Event::Studied) {
+            label_status.set_text(&shared_state.lock().unwrap().goals.status());
+        }
+    });
+
+    v_box
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goals(day: &str, streak: u32, last_kept: &str, freezes: u32) -> Goals {
+        Goals {
+            kind: GoalKind::Cards,
+            target: 2,
+            max_freezes: 2,
+            freezes,
+            streak,
+            last_kept: Some(last_kept.to_string()),
+            day: day.to_string(),
+            cards: 0,
+            seconds: 0,
+            last_activity: None,
+        }
+    }
+
+    #[test]
+    fn days_between_months_and_years() {
+        assert_eq!(days_between("2024-02-28", "2024-03-01"), Some(2));
+        assert_eq!(days_between("2023-12-31", "2024-01-01"), Some(1));
+        assert_eq!(days_between("2024-03-01", "2024-03-01"), Some(0));
+        assert_eq!(days_between("2024-03-01", "someday"), None);
+        assert_eq!(day_before("2024-03-01").as_deref(), Some("2024-02-29"));
+    }
+
+    #[test]
+    fn next_day_keeps_streak() {
+        let mut g = goals("2024-03-01", 4, "2024-03-01", 1);
+        g.cards = 5;
+        assert!(!g.roll_over_to("2024-03-02"));
+        assert_eq!((g.day.as_str(), g.cards), ("2024-03-02", 0));
+        assert_eq!((g.streak, g.freezes), (4, 1));
+    }
+
+    #[test]
+    fn freezes_cover_missed_days() {
+        let mut g = goals("2024-03-01", 4, "2024-03-01", 2);
+        assert!(g.roll_over_to("2024-03-04"));
+        assert_eq!((g.streak, g.freezes), (4, 0));
+        assert_eq!(g.last_kept.as_deref(), Some("2024-03-03"));
+        // Covered, so the next day needs no more.
+        assert!(!g.roll_over_to("2024-03-04"));
+    }
+
+    #[test]
+    fn too_many_missed_days_break_streak() {
+        let mut g = goals("2024-03-01", 4, "2024-03-01", 1);
+        assert!(g.roll_over_to("2024-03-04"));
+        assert_eq!((g.streak, g.freezes), (0, 1));
+        assert_eq!(g.last_kept, None);
+    }
+
+    #[test]
+    fn freeze_earned_every_freeze_every_days() {
+        let mut g = goals("2024-03-08", FREEZE_EVERY - 1, "2024-03-07", 0);
+        let now = Instant::now();
+        g.count_card(now);
+        assert_eq!((g.streak, g.freezes), (FREEZE_EVERY - 1, 0));
+        g.count_card(now);
+        assert_eq!((g.streak, g.freezes), (FREEZE_EVERY, 1));
+        // Once a day only.
+        g.count_card(now);
+        assert_eq!(g.streak, FREEZE_EVERY);
+
+        let mut full = goals("2024-03-08", FREEZE_EVERY - 1, "2024-03-07", 2);
+        full.count_card(now);
+        full.count_card(now);
+        assert_eq!(full.freezes, 2);
+    }
+}
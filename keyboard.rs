@@ -0,0 +1,270 @@
+use gtk::prelude::*;
+use gtk::{Button, Label, ToggleButton};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Kedmanee (TIS 820-2538) layout, one entry per key: (unshifted, shifted).
+const ROWS: [&[(char, char)]; 4] = [
+    &[
+        ('_', '%'),
+        ('ๅ', '+'),
+        ('/', '๑'),
+        ('-', '๒'),
+        ('ภ', '๓'),
+        ('ถ', '๔'),
+        ('ุ', 'ู'),
+        ('ึ', '฿'),
+        ('ค', '๕'),
+        ('ต', '๖'),
+        ('จ', '๗'),
+        ('ข', '๘'),
+        ('ช', '๙'),
+    ],
+    &[
+        ('ๆ', '๐'),
+        ('ไ', '"'),
+        ('ำ', 'ฎ'),
+        ('พ', 'ฑ'),
+        ('ะ', 'ธ'),
+        ('ั', 'ํ'),
+        ('ี', '๊'),
+        ('ร', 'ณ'),
+        ('น', 'ฯ'),
+        ('ย', 'ญ'),
+        ('บ', 'ฐ'),
+        ('ล', ','),
+        ('ฃ', 'ฅ'),
+    ],
+    &[
+        ('ฟ', 'ฤ'),
+        ('ห', 'ฆ'),
+        ('ก', 'ฏ'),
+        ('ด', 'โ'),
+        ('เ', 'ฌ'),
+        ('้', '็'),
+        ('่', '๋'),
+        ('า', 'ษ'),
+        ('ส', 'ศ'),
+        ('ว', 'ซ'),
+        ('ง', '.'),
+    ],
+    &[
+        ('ผ', '('),
+        ('ป', ')'),
+        ('แ', 'ฉ'),
+        ('อ', 'ฮ'),
+        ('ิ', 'ฺ'),
+        ('ื', '์'),
+        ('ท', '?'),
+        ('ม', 'ฒ'),
+        ('ใ', 'ฬ'),
+        ('ฝ', 'ฦ'),
+    ],
+];
+
+const CSS: &[u8] = b"
+button.key-highlight { background-image: none; background-color: #8fd18f; }
+button.key-highlight-shift { background-image: none; background-color: #f3c969; }
+";
+
+type KeyHandler = Rc<RefCell<Option<Box<dyn Fn(char)>>>>;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Layer {
+    Base,
+    Shift,
+}
+
+/// Where a character is typed on the Kedmanee layout.
+pub fn locate(c: char) -> Option<(usize, usize, Layer)> {
+    for (row, keys) in ROWS.iter().enumerate() {
+        for (col, (base, shift)) in keys.iter().enumerate() {
+            if *base == c {
+                return Some((row, col, Layer::Base));
+            }
+            if *shift == c {
+                return Some((row, col, Layer::Shift));
+            }
+        }
+    }
+    None
+}
+
+/// Combining marks are drawn on a dotted circle so they are visible on a key cap.
+fn key_face(c: char) -> String {
+    if is_combining(c) {
+        format!("\u{25CC}{}", c)
+    } else {
+        c.to_string()
+    }
+}
+
+fn is_combining(c: char) -> bool {
+    matches!(c, '\u{0E31}' | '\u{0E34}'..='\u{0E3A}' | '\u{0E47}'..='\u{0E4E}')
+}
+
+fn escape(c: char) -> String {
+    pango::glib::markup_escape_text(&key_face(c)).to_string()
+}
+
+#[derive(Clone)]
+pub struct Keyboard {
+    pub widget: gtk::Box,
+    keys: Vec<Vec<Button>>,
+    shift_keys: Vec<ToggleButton>,
+    on_key: KeyHandler,
+}
+
+impl Keyboard {
+    pub fn new() -> Keyboard {
+        let provider = gtk::CssProvider::new();
+        provider.load_from_data(CSS).unwrap();
+        gtk::StyleContext::add_provider_for_screen(
+            &gtk::gdk::Screen::default().unwrap(),
+            &provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        let on_key: KeyHandler = Rc::new(RefCell::new(None));
+        let shift_keys = vec![
+            ToggleButton::with_label("Shift"),
+            ToggleButton::with_label("Shift"),
+        ];
+        let mut keys = Vec::new();
+
+        for (row, row_keys) in ROWS.iter().enumerate() {
+            let h_box = gtk::Box::new(gtk::Orientation::Horizontal, 2);
+            h_box.set_halign(gtk::Align::Center);
+            if row == 3 {
+                h_box.pack_start(&shift_keys[0], false, false, 0);
+            }
+
+            let mut buttons = Vec::new();
+            for (base, shift) in row_keys.iter().copied() {
+                let label = Label::new(None);
+                label.set_markup(&format!(
+                    "<span font_desc='Noto Looped Thai UI Normal 8'>{}</span>\n<span font_desc='Noto Looped Thai UI Normal 14'>{}</span>",
+                    escape(shift),
+                    escape(base)
+                ));
+                label.set_justify(gtk::Justification::Center);
+
+                let button = Button::new();
+                button.add(&label);
+                button.set_size_request(44, 44);
+                button.set_can_focus(false);
+
+                let on_key_1 = on_key.clone();
+                let shift_keys_1 = shift_keys.clone();
+                button.connect_clicked(move |_| {
+                    let shifted = shift_keys_1.iter().any(|k| k.is_active());
+                    for k in &shift_keys_1 {
+                        k.set_active(false);
+                    }
+                    if let Some(f) = on_key_1.borrow().as_ref() {
+                        f(if shifted { shift } else { base });
+                    }
+                });
+
+                h_box.pack_start(&button, false, false, 0);
+                buttons.push(button);
+            }
+
+            if row == 3 {
+                h_box.pack_start(&shift_keys[1], false, false, 0);
+            }
+            widget.pack_start(&h_box, false, false, 0);
+            keys.push(buttons);
+        }
+
+        let space = Button::with_label(" ");
+        space.set_size_request(300, 36);
+        space.set_can_focus(false);
+        let on_key_2 = on_key.clone();
+        space.connect_clicked(move |_| {
+            if let Some(f) = on_key_2.borrow().as_ref() {
+                f(' ');
+            }
+        });
+        let h_box_space = gtk::Box::new(gtk::Orientation::Horizontal, 2);
+        h_box_space.set_halign(gtk::Align::Center);
+        h_box_space.pack_start(&space, false, false, 0);
+        widget.pack_start(&h_box_space, false, false, 0);
+
+        for k in &shift_keys {
+            k.set_can_focus(false);
+        }
+
+        Keyboard {
+            widget,
+            keys,
+            shift_keys,
+            on_key,
+        }
+    }
+
+    /// Called with the typed character whenever a key on the widget is clicked.
+    pub fn connect_key<F: Fn(char) + 'static>(&self, f: F) {
+        *self.on_key.borrow_mut() = Some(Box::new(f));
+    }
+
+    pub fn clear(&self) {
+        for button in self.keys.iter().flatten() {
+            button.style_context().remove_class("key-highlight");
+            button.style_context().remove_class("key-highlight-shift");
+        }
+        for k in &self.shift_keys {
+            k.style_context().remove_class("key-highlight-shift");
+        }
+    }
+
+    /// Highlights the keys needed to type `text`; keys on the Shift layer are
+    /// drawn in a different colour together with both Shift keys.
+    pub fn highlight(&self, text: &str) {
+        self.clear();
+
+        for c in text.chars() {
+            if let Some((row, col, layer)) = locate(c) {
+                let button = &self.keys[row][col];
+                match layer {
+                    Layer::Base => button.style_context().add_class("key-highlight"),
+                    Layer::Shift => {
+                        button.style_context().add_class("key-highlight-shift");
+                        for k in &self.shift_keys {
+                            k.style_context().add_class("key-highlight-shift");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_keys() {
+        assert!(locate('ก') == Some((2, 2, Layer::Base)));
+        assert!(locate('ฮ') == Some((3, 3, Layer::Shift)));
+        assert!(locate('a').is_none());
+        assert_eq!(key_face('ี'), "\u{25CC}ี");
+        assert_eq!(key_face('ก'), "ก");
+    }
+
+    #[test]
+    fn every_deck_character_has_one_key() {
+        let mut seen = std::collections::HashSet::new();
+        for (base, shift) in ROWS.iter().flat_map(|keys| keys.iter()) {
+            assert!(seen.insert(*base), "{} twice", base);
+            assert!(seen.insert(*shift), "{} twice", shift);
+        }
+        for letter in crate::get_letters() {
+            for c in letter.glyphs().chars() {
+                assert!(locate(c).is_some(), "{} of {}", c, letter.letter);
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::storage;
+use crate::Letter;
+use gtk::prelude::*;
+use gtk::Label;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub const BOXES: usize = 5;
+const LEITNER_FILE: &str = "leitner.tsv";
+
+/// Leitner boxes: every letter starts in box 1, moves up one box when
+/// answered correctly and back to box 1 when missed. Box n is reviewed every
+/// 2^(n-1) rounds.
+#[derive(Clone, Default)]
+pub struct Leitner {
+    boxes: HashMap<String, usize>,
+    round: usize,
+    queue: Vec<usize>,
+    /// Where the boxes are kept; None for boxes that aren't saved.
+    file: Option<PathBuf>,
+}
+
+fn is_due(n: usize, round: usize) -> bool {
+    round.is_multiple_of(1 << (n - 1))
+}
+
+impl Leitner {
+    pub fn load() -> Leitner {
+        let file = storage::data_file(LEITNER_FILE);
+        let mut leitner = Leitner {
+            file: Some(file.clone()),
+            ..Leitner::default()
+        };
+        for record in storage::read_records(&file) {
+            match record.as_slice() {
+                [kind, round] if kind == "round" => {
+                    leitner.round = round.parse().unwrap_or(0);
+                }
+                [kind, letter, n] if kind == "box" => {
+                    if let Ok(n) = n.parse::<usize>() {
+                        leitner.boxes.insert(letter.clone(), n.clamp(1, BOXES));
+                    }
+                }
+                _ => (),
+            }
+        }
+        leitner
+    }
+
+    pub fn save(&self) {
+        let file = match &self.file {
+            Some(file) => file,
+            None => return,
+        };
+        let mut records = vec![vec![String::from("round"), self.round.to_string()]];
+        let mut boxes: Vec<(&String, &usize)> = self.boxes.iter().collect();
+        boxes.sort();
+        for (letter, n) in boxes {
+            records.push(vec![String::from("box"), letter.clone(), n.to_string()]);
+        }
+        storage::write_records(file, &records);
+    }
+
+    pub fn box_of(&self, letter: &str) -> usize {
+        *self.boxes.get(letter).unwrap_or(&1)
+    }
+
+    pub fn promote(&mut self, letter: &str) {
+        let n = (self.box_of(letter) + 1).min(BOXES);
+        self.boxes.insert(letter.to_string(), n);
+        self.save();
+    }
+
+    pub fn demote(&mut self, letter: &str) {
+        self.boxes.insert(letter.to_string(), 1);
+        self.save();
+    }
+
+    /// Number of letters of `indices` in each box.
+    pub fn counts(&self, letters: &[Letter], indices: &[usize]) -> [usize; BOXES] {
+        let mut counts = [0; BOXES];
+        for i in indices {
+            counts[self.box_of(&letters[*i].letter) - 1] += 1;
+        }
+        counts
+    }
+
+    /// The next card due for review. When the current round is done, the
+    /// next round with any due box is started.
+    pub fn next_card(&mut self, letters: &[Letter], indices: &[usize]) -> Option<usize> {
+        self.queue.retain(|i| indices.contains(i));
+
+        // After 2^(BOXES-1) rounds every box has been due at least once.
+        for _ in 0..=(1 << (BOXES - 1)) {
+            if let Some(i) = self.queue.pop() {
+                return Some(i);
+            }
+
+            self.round += 1;
+            self.queue = indices
+                .iter()
+                .copied()
+                .filter(|i| is_due(self.box_of(&letters[*i].letter), self.round))
+                .collect();
+            self.queue.shuffle(&mut rand::thread_rng());
+            self.save();
+        }
+        None
+    }
+}
+
+/// A row of boxes with the number of letters in each; the box of the current
+/// letter is highlighted.
+#[derive(Clone)]
+pub struct BoxIndicator {
+    pub widget: gtk::Box,
+    labels: Vec<Label>,
+}
+
+impl BoxIndicator {
+    pub fn new() -> BoxIndicator {
+        let widget = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+        let mut labels = Vec::new();
+        for _ in 0..BOXES {
+            let label = Label::new(None);
+            widget.pack_start(&label, true, false, 0);
+            labels.push(label);
+        }
+        BoxIndicator { widget, labels }
+    }
+
+    pub fn update(&self, counts: [usize; BOXES], current: usize) {
+        for (n, label) in self.labels.iter().enumerate() {
+            let txt = format!("Box {}\n{} letters", n + 1, counts[n]);
+            if n + 1 == current {
+                label.set_markup(&format!(
+                    "<span weight='bold' background='#f3c969'>{}</span>",
+                    txt
+                ));
+            } else {
+                label.set_markup(&txt);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn promote_and_demote() {
+        let mut leitner = Leitner::default();
+        assert_eq!(leitner.box_of("ก"), 1);
+        leitner.promote("ก");
+        leitner.promote("ก");
+        assert_eq!(leitner.box_of("ก"), 3);
+        for _ in 0..BOXES {
+            leitner.promote("ก");
+        }
+        assert_eq!(leitner.box_of("ก"), BOXES);
+        leitner.demote("ก");
+        assert_eq!(leitner.box_of("ก"), 1);
+    }
+
+    #[test]
+    fn higher_boxes_come_round_less_often() {
+        let due = |n| (1..=16).filter(|round| is_due(n, *round)).count();
+        assert_eq!(due(1), 16);
+        assert_eq!(due(2), 8);
+        assert_eq!(due(3), 4);
+        assert_eq!(due(BOXES), 1);
+    }
+
+    #[test]
+    fn only_due_cards_are_shown() {
+        let letters = crate::get_letters();
+        let mut leitner = Leitner::default();
+        leitner.promote(&letters[1].letter);
+        let indices = [0, 1, 2];
+
+        // Round 1: box 2 isn't due.
+        let mut shown: Vec<usize> = (0..2)
+            .map(|_| leitner.next_card(&letters, &indices).unwrap())
+            .collect();
+        shown.sort();
+        assert_eq!(shown, [0, 2]);
+        // Round 2: all are.
+        let mut shown: Vec<usize> = (0..3)
+            .map(|_| leitner.next_card(&letters, &indices).unwrap())
+            .collect();
+        shown.sort();
+        assert_eq!(shown, [0, 1, 2]);
+        assert_eq!(leitner.counts(&letters, &indices), [2, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn no_cards_no_review() {
+        let letters = crate::get_letters();
+        assert_eq!(Leitner::default().next_card(&letters, &[]), None);
+    }
+}
@@ -1,13 +1,51 @@
+mod audio;
+mod class_sort;
+mod confusables;
+mod deck;
+mod dictation;
+mod events;
+mod exam;
+mod goals;
+mod keyboard;
+mod leitner;
+mod manifest;
+mod memory;
+mod paths;
+mod pitch;
+mod record;
+mod session;
+mod settings;
+mod speech;
+mod speed_round;
+mod storage;
+mod stretch;
+mod study_sets;
+mod syllables;
+mod thai;
+mod tone_score;
+mod tones;
+mod traversal;
+mod typing;
+mod unlock;
+
+use audio::Audio;
+use deck::Deck;
+use events::Event;
+use goals::Goals;
 use gtk::{prelude::*, Label};
-use gtk::{Application, ApplicationWindow, Button};
+use gtk::{Application, ApplicationWindow, Button, ComboBoxText};
+use keyboard::Keyboard;
+use leitner::{BoxIndicator, Leitner};
+use manifest::Manifest;
 use pango::glib::random_int_range;
-use rodio::{Decoder, OutputStream, Sink};
+use session::Session;
+use settings::Settings;
 use std::fmt;
-use std::fs::File;
-use std::io::BufReader;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use std::thread;
+use thai::{ConsonantClass, Syllable};
+use traversal::ShuffledCycle;
+use unlock::Unlock;
 
 struct Letter {
     letter: String,
@@ -21,6 +59,34 @@ struct Letter {
 #[derive(Clone)]
 struct Context {
     curr_index: usize,
+    deck: Deck,
+    session: Session,
+    leitner: Leitner,
+    leitner_mode: bool,
+    goals: Goals,
+    unlock: Unlock,
+    audio: Audio,
+    /// Which clips each entry has.
+    manifest: Manifest,
+    settings: Settings,
+    /// The generated syllable on screen instead of a card, if any.
+    syllable: Option<Syllable>,
+    shuffle: Option<ShuffledCycle>,
+}
+
+impl Context {
+    /// Records an answer in the session and towards unlocking new letters.
+    fn answer(&mut self, expected: &str, given: Option<&str>, correct: bool) {
+        self.session.answer(expected, given, correct);
+        self.unlock.record(expected, correct);
+        events::emit(Event::Studied);
+    }
+
+    /// The cards of the current deck that have been unlocked, or the whole
+    /// deck when none of it is unlocked yet.
+    fn indices(&self, letters: &[Letter]) -> Vec<usize> {
+        self.unlock.unlocked_of(letters, &self.deck.indices)
+    }
 }
 
 impl fmt::Display for Letter {
@@ -47,19 +113,12 @@ impl fmt::Display for Letter {
     }
 }
 
-fn play(letters_rc: Arc<Mutex<Vec<Letter>>>, current_index: usize) {
-    thread::spawn(move || {
-        let binding = letters_rc.lock().unwrap();
-        let l = &binding.get(current_index).unwrap();
-        l.play_letter();
-    });
-}
-
 fn compose_view(
     l: &&Letter,
     label_1: &Label,
     label_3: &Label,
     label_4: &Label,
+    keyboard: &Keyboard,
 ) -> () {
     let label_markup_1 = format!(
         "<span font_desc='{} Normal 40'>{}</span>  <span font_desc='{} Normal 30'>{}</span>",
@@ -81,6 +140,48 @@ fn compose_view(
 
     label_3.set_markup(&txt);
     label_3.hide();
+
+    keyboard.highlight(&l.glyphs());
+}
+
+fn update_boxes(state: &Context, letters: &[Letter], indicator: &BoxIndicator) {
+    indicator.update(
+        state.leitner.counts(letters, &state.indices(letters)),
+        state.leitner.box_of(&letters[state.curr_index].letter),
+    );
+}
+
+/// Plays the recording of what is on screen; a syllable may have none.
+fn play_current(state: &Context, letters: &[Letter]) {
+    let entry = match &state.syllable {
+        Some(syllable) => syllable.written(),
+        None => letters[state.curr_index].letter.clone(),
+    };
+    state.audio.play(&state.manifest.main_clip(&entry));
+}
+
+fn deck_names(letters: &[Letter]) -> Vec<String> {
+    deck::all_decks(letters).into_iter().map(|d| d.name).collect()
+}
+
+/// Lists every deck and keeps the current one selected if it still exists.
+/// Selecting emits `changed`, so the letters must not be locked meanwhile.
+fn fill_deck_combo(combo: &ComboBoxText, names: &[String]) {
+    let active = combo.active_id();
+    combo.remove_all();
+    for name in names {
+        combo.append(Some(name), name);
+    }
+    if active.is_none() || !combo.set_active_id(active.as_deref()) {
+        combo.set_active(Some(0));
+    }
+}
+
+fn end_session(app: &Application, shared_state: &Arc<Mutex<Context>>) {
+    let summary = shared_state.lock().unwrap().session.end();
+    if let Some(summary) = summary {
+        session::build_summary_window(app, &summary);
+    }
 }
 
 fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> ApplicationWindow {
@@ -96,35 +197,140 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
     let button_next = Button::with_label("Next");
     let button_prev = Button::with_label("Previous");
     let button_random = Button::with_label("Random");
+    let button_syllable = Button::with_label("Syllable");
     let button_show = Button::with_label("Show");
+    let button_knew = Button::with_label("Knew it");
+    let button_missed = Button::with_label("Didn't know");
+    let button_end = Button::with_label("End session");
+    let button_sets = Button::with_label("Study sets");
+    let combo_deck = ComboBoxText::new();
+    let check_leitner = gtk::CheckButton::with_label("Leitner boxes");
+    let check_shuffle = gtk::CheckButton::with_label("Random without repeats");
+    let check_play_navigation = gtk::CheckButton::with_label("Play when a card is shown");
+    let check_play_reveal = gtk::CheckButton::with_label("Play on Show");
+    let button_audio_folder =
+        gtk::FileChooserButton::new("Custom audio folder", gtk::FileChooserAction::SelectFolder);
+    button_audio_folder.set_tooltip_text(Some("Recordings here replace the built-in ones"));
+    let button_builtin_audio = Button::with_label("Built-in audio");
+    let label_speed = Label::new(Some("Speed"));
+    let scale_speed = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.5, 1.5, 0.1);
+    scale_speed.set_width_request(150);
+    scale_speed.add_mark(1.0, gtk::PositionType::Bottom, None);
+    let button_reshuffle = Button::with_label("Reshuffle");
+    let entry_seed = gtk::Entry::new();
+    entry_seed.set_placeholder_text(Some("Seed"));
+    entry_seed.set_width_chars(12);
+    let label_round = Label::new(None);
+    let button_typing = Button::with_label("Typing drill");
+    let button_classes = Button::with_label("Consonant classes");
+    let button_tones = Button::with_label("Tone drill");
+    let button_speed = Button::with_label("Speed round");
+    let button_confusables = Button::with_label("Look-alikes");
+    let button_dictation = Button::with_label("Dictation");
+    let button_memory = Button::with_label("Memory");
+    let button_exam = Button::with_label("Exam");
 
     let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
     let h_box_letters = gtk::Box::new(gtk::Orientation::Horizontal, 10);
     let h_box_show_hide = gtk::Box::new(gtk::Orientation::Horizontal, 10);
     let h_box_buttons = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let h_box_modes = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let h_box_shuffle = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let h_box_cycle = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let h_box_audio = gtk::Box::new(gtk::Orientation::Horizontal, 10);
 
     let label_1 = Label::new(Some(""));
     let label_3 = Label::new(Some(""));
     label_3.hide();
     let label_4 = Label::new(Some(""));
     label_4.hide();
+    let keyboard = Keyboard::new();
+    let box_indicator = BoxIndicator::new();
 
     h_box_letters.pack_start(&label_1, true, true, 0);
     h_box_letters.set_hexpand(true);
 
     h_box_show_hide.pack_start(&button_show, true, false, 0);
+    h_box_show_hide.pack_start(&button_knew, true, false, 0);
+    h_box_show_hide.pack_start(&button_missed, true, false, 0);
+    let entries: Vec<String> = letters.iter().map(|l| l.letter.clone()).collect();
+    h_box_show_hide.pack_start(
+        &manifest::build_clip_buttons(entries.clone(), Arc::clone(&shared_state)),
+        true,
+        false,
+        0,
+    );
+    h_box_show_hide.pack_start(
+        &record::build_recorder(entries.clone(), Arc::clone(&shared_state)),
+        true,
+        false,
+        0,
+    );
     h_box_show_hide.set_hexpand(true);
 
+    h_box_buttons.pack_start(&combo_deck, true, false, 0);
     h_box_buttons.pack_start(&button_prev, true, false, 0);
     h_box_buttons.pack_start(&button_next, true, false, 0);
     h_box_buttons.pack_start(&button_random, true, false, 0);
+    h_box_buttons.pack_start(&button_syllable, true, false, 0);
+    h_box_buttons.pack_start(&check_leitner, true, false, 0);
     h_box_buttons.set_hexpand(true);
 
+    h_box_cycle.pack_start(&label_round, true, false, 0);
+    h_box_cycle.pack_start(&entry_seed, false, false, 0);
+    h_box_cycle.pack_start(&button_reshuffle, false, false, 0);
+    h_box_shuffle.pack_start(&check_shuffle, true, false, 0);
+    h_box_shuffle.pack_start(&h_box_cycle, true, false, 0);
+    h_box_shuffle.set_hexpand(true);
+
+    h_box_audio.pack_start(&check_play_navigation, true, false, 0);
+    h_box_audio.pack_start(&check_play_reveal, true, false, 0);
+    h_box_audio.pack_start(&button_audio_folder, false, false, 0);
+    h_box_audio.pack_start(&button_builtin_audio, false, false, 0);
+    h_box_audio.pack_start(&label_speed, false, false, 0);
+    h_box_audio.pack_start(&scale_speed, false, false, 0);
+    h_box_audio.set_hexpand(true);
+
+    h_box_modes.pack_start(&button_typing, true, false, 0);
+    h_box_modes.pack_start(&button_classes, true, false, 0);
+    h_box_modes.pack_start(&button_tones, true, false, 0);
+    h_box_modes.pack_start(&button_speed, true, false, 0);
+    h_box_modes.pack_start(&button_confusables, true, false, 0);
+    h_box_modes.pack_start(&button_dictation, true, false, 0);
+    h_box_modes.pack_start(&button_memory, true, false, 0);
+    h_box_modes.pack_start(&button_exam, true, false, 0);
+    h_box_modes.pack_start(&button_sets, true, false, 0);
+    h_box_modes.pack_start(&button_end, true, false, 0);
+    h_box_modes.set_hexpand(true);
+
     v_box.pack_start(&h_box_letters, false, false, 0);
+    v_box.pack_start(
+        &pitch::build_pitch_view(entries, Arc::clone(&shared_state)),
+        false,
+        false,
+        0,
+    );
+    v_box.pack_start(&box_indicator.widget, false, false, 0);
     v_box.pack_start(&label_4, false, false, 0);
     v_box.pack_start(&label_3, false, false, 0);
     v_box.pack_start(&h_box_show_hide, false, false, 0);
     v_box.pack_start(&h_box_buttons, false, false, 0);
+    v_box.pack_start(&h_box_shuffle, false, false, 0);
+    v_box.pack_start(&h_box_audio, false, false, 0);
+    v_box.pack_start(&keyboard.widget, false, false, 0);
+    v_box.pack_start(&h_box_modes, false, false, 0);
+    v_box.pack_start(
+        &unlock::build_unlock_status(&letters, Arc::clone(&shared_state)),
+        false,
+        false,
+        0,
+    );
+    v_box.pack_start(
+        &goals::build_goal_status(Arc::clone(&shared_state)),
+        false,
+        false,
+        0,
+    );
 
     let label_1_rc = Rc::new(label_1);
     let label_3_rc = Rc::new(label_3);
@@ -136,20 +342,48 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
     let label_1_rc_1 = label_1_rc.clone();
     let label_3_rc_1 = label_3_rc.clone();
     let label_4_rc_1 = label_4_rc.clone();
+    let keyboard_1 = keyboard.clone();
+    let box_indicator_1 = box_indicator.clone();
     button_next.connect_clicked(move |_| {
         let mut current_index = shared_state_clone_1.lock().unwrap().curr_index;
 
-        if current_index < letters_rc_1.lock().unwrap().len() - 1 {
-            shared_state_clone_1.lock().unwrap().curr_index += 1;
-            current_index += 1;
+        if shared_state_clone_1.lock().unwrap().leitner_mode {
+            let binding = letters_rc_1.lock().unwrap();
+            let mut state = shared_state_clone_1.lock().unwrap();
+            let indices = state.indices(&binding);
+            current_index = state
+                .leitner
+                .next_card(&binding, &indices)
+                .unwrap_or(current_index);
+        } else {
+            let indices = shared_state_clone_1
+                .lock()
+                .unwrap()
+                .indices(&letters_rc_1.lock().unwrap());
+            current_index = match indices.iter().position(|i| *i == current_index) {
+                Some(pos) if pos < indices.len() - 1 => indices[pos + 1],
+                Some(_) => current_index,
+                None => indices[0],
+            };
         }
+        shared_state_clone_1.lock().unwrap().curr_index = current_index;
         let binding = letters_rc_1.lock().unwrap();
         compose_view(
             &binding.get(current_index).unwrap(),
             &label_1_rc_1,
             &label_3_rc_1,
             &label_4_rc_1,
+            &keyboard_1,
         );
+        let mut state = shared_state_clone_1.lock().unwrap();
+        state.session.show_card(&binding[current_index].letter);
+        state.syllable = None;
+        state.goals.study();
+        events::emit(Event::CardShown);
+        if state.settings.play_on_navigation {
+            play_current(&state, &binding);
+        }
+        update_boxes(&state, &binding, &box_indicator_1);
     });
 
     let letters_rc_2 = letters_rc.clone();
@@ -157,13 +391,23 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
     let label_1_rc_2 = label_1_rc.clone();
     let label_3_rc_2 = label_3_rc.clone();
     let label_4_rc_2 = label_4_rc.clone();
+    let keyboard_2 = keyboard.clone();
+    let box_indicator_2 = box_indicator.clone();
     button_prev.connect_clicked(move |_| {
         let mut current_index = shared_state_clone_2.lock().unwrap().curr_index;
 
-        if current_index > 0 {
-            shared_state_clone_2.lock().unwrap().curr_index -= 1;
-            current_index -= 1;
+        {
+            let indices = shared_state_clone_2
+                .lock()
+                .unwrap()
+                .indices(&letters_rc_2.lock().unwrap());
+            current_index = match indices.iter().position(|i| *i == current_index) {
+                Some(pos) if pos > 0 => indices[pos - 1],
+                Some(_) => current_index,
+                None => indices[0],
+            };
         }
+        shared_state_clone_2.lock().unwrap().curr_index = current_index;
 
         let binding = letters_rc_2.lock().unwrap();
         compose_view(
@@ -171,7 +415,17 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
             &label_1_rc_2,
             &label_3_rc_2,
             &label_4_rc_2,
+            &keyboard_2,
         );
+        let mut state = shared_state_clone_2.lock().unwrap();
+        state.session.show_card(&binding[current_index].letter);
+        state.syllable = None;
+        state.goals.study();
+        events::emit(Event::CardShown);
+        if state.settings.play_on_navigation {
+            play_current(&state, &binding);
+        }
+        update_boxes(&state, &binding, &box_indicator_2);
     });
 
     let letters_rc_3 = letters_rc.clone();
@@ -179,8 +433,25 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
     let label_1_rc_3 = label_1_rc.clone();
     let label_3_rc_3 = label_3_rc.clone();
     let label_4_rc_3 = label_4_rc.clone();
+    let keyboard_3 = keyboard.clone();
+    let box_indicator_3 = box_indicator.clone();
+    let label_round_3 = label_round.clone();
     button_random.connect_clicked(move |_| {
-        let r = random_int_range(0, letters_rc_3.lock().unwrap().len() as i32);
+        let mut state = shared_state_clone_3.lock().unwrap();
+        let indices = state.indices(&letters_rc_3.lock().unwrap());
+        let r = match state.shuffle.as_mut() {
+            Some(cycle) => {
+                // The deck or the unlocked letters changed since it started.
+                if cycle.indices() != indices.as_slice() {
+                    *cycle = ShuffledCycle::new(cycle.seed(), &indices);
+                }
+                let r = cycle.next().unwrap_or(indices[0]) as i32;
+                label_round_3.set_text(&cycle.progress());
+                r
+            }
+            None => indices[random_int_range(0, indices.len() as i32) as usize] as i32,
+        };
+        drop(state);
         shared_state_clone_3.lock().unwrap().curr_index = r as usize;
 
         let binding = letters_rc_3.lock().unwrap();
@@ -189,7 +460,114 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
             &label_1_rc_3,
             &label_3_rc_3,
             &label_4_rc_3,
+            &keyboard_3,
         );
+        let mut state = shared_state_clone_3.lock().unwrap();
+        state.session.show_card(&binding[r as usize].letter);
+        state.syllable = None;
+        state.goals.study();
+        events::emit(Event::CardShown);
+        if state.settings.play_on_navigation {
+            play_current(&state, &binding);
+        }
+        update_boxes(&state, &binding, &box_indicator_3);
+    });
+
+    let letters_rc_21 = letters_rc.clone();
+    let shared_state_clone_21 = Arc::clone(&shared_state);
+    let label_1_rc_21 = label_1_rc.clone();
+    let label_3_rc_21 = label_3_rc.clone();
+    let label_4_rc_21 = label_4_rc.clone();
+    let keyboard_21 = keyboard.clone();
+    button_syllable.connect_clicked(move |_| {
+        let binding = letters_rc_21.lock().unwrap();
+        let unlocked = shared_state_clone_21
+            .lock()
+            .unwrap()
+            .unlock
+            .unlocked(&binding);
+        let syllable = syllables::new_syllable(&binding, &unlocked);
+        let written = syllable.written();
+
+        label_1_rc_21.set_markup(&format!(
+            "<span font_desc='Noto Looped Thai UI Normal 60'>{}</span>",
+            written
+        ));
+        label_4_rc_21.set_markup(&format!(
+            "Romanization: {}",
+            syllables::romanization(&syllable, &binding)
+        ));
+        label_4_rc_21.hide();
+        label_3_rc_21.set_text(&format!(
+            "{} tone\n{}",
+            syllable.tone(),
+            syllable.reasoning().join("\n")
+        ));
+        label_3_rc_21.hide();
+        keyboard_21.highlight(&written);
+
+        let mut state = shared_state_clone_21.lock().unwrap();
+        state.session.show_card(&written);
+        state.goals.study();
+        state.syllable = Some(syllable);
+        events::emit(Event::CardShown);
+        if state.settings.play_on_navigation {
+            play_current(&state, &binding);
+        }
+    });
+
+    {
+        let mut state = shared_state.lock().unwrap();
+        check_play_navigation.set_active(state.settings.play_on_navigation);
+        check_play_reveal.set_active(state.settings.play_on_reveal);
+        scale_speed.set_value(state.settings.speed as f64);
+        state.audio.set_speed(state.settings.speed);
+        if let Some(folder) = &state.settings.audio_folder {
+            button_audio_folder.set_filename(folder);
+        }
+        state.audio.set_folder(state.settings.audio_folder.clone());
+        state.manifest = Manifest::load(state.settings.audio_folder.as_deref());
+    }
+    let shared_state_clone_25 = Arc::clone(&shared_state);
+    check_play_navigation.connect_toggled(move |check| {
+        let mut state = shared_state_clone_25.lock().unwrap();
+        state.settings.play_on_navigation = check.is_active();
+        state.settings.save();
+    });
+    let shared_state_clone_26 = Arc::clone(&shared_state);
+    check_play_reveal.connect_toggled(move |check| {
+        let mut state = shared_state_clone_26.lock().unwrap();
+        state.settings.play_on_reveal = check.is_active();
+        state.settings.save();
+    });
+
+    let shared_state_clone_27 = Arc::clone(&shared_state);
+    scale_speed.connect_value_changed(move |scale| {
+        let mut state = shared_state_clone_27.lock().unwrap();
+        state.settings.speed = scale.value() as f32;
+        state.audio.set_speed(state.settings.speed);
+        state.settings.save();
+    });
+
+    let shared_state_clone_28 = Arc::clone(&shared_state);
+    button_audio_folder.connect_file_set(move |button| {
+        let mut state = shared_state_clone_28.lock().unwrap();
+        state.settings.audio_folder = button.filename();
+        state.audio.set_folder(state.settings.audio_folder.clone());
+        state.manifest = Manifest::load(state.settings.audio_folder.as_deref());
+        state.settings.save();
+        events::emit(Event::AudioChanged);
+    });
+    let shared_state_clone_29 = Arc::clone(&shared_state);
+    let button_audio_folder_29 = button_audio_folder.clone();
+    button_builtin_audio.connect_clicked(move |_| {
+        button_audio_folder_29.unselect_all();
+        let mut state = shared_state_clone_29.lock().unwrap();
+        state.settings.audio_folder = None;
+        state.audio.set_folder(None);
+        state.manifest = Manifest::load(None);
+        state.settings.save();
+        events::emit(Event::AudioChanged);
     });
 
     let letters_rc_4 = letters_rc.clone();
@@ -199,16 +577,275 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
     button_show.connect_clicked(move |_| {
         label_4_rc_4.show();
         label_3_rc_4.show();
+        shared_state_clone_4.lock().unwrap().session.reveal();
 
-        let letters_rc_2_clone = Arc::clone(&letters_rc_4);
-        play(
-            letters_rc_2_clone,
-            shared_state_clone_4.lock().unwrap().curr_index,
+        let state = shared_state_clone_4.lock().unwrap();
+        if state.settings.play_on_reveal {
+            play_current(&state, &letters_rc_4.lock().unwrap());
+        }
+    });
+
+    for (button, knew) in [(&button_knew, true), (&button_missed, false)] {
+        let letters_rc_9 = letters_rc.clone();
+        let shared_state_clone_9 = Arc::clone(&shared_state);
+        let button_next_9 = button_next.clone();
+        let button_syllable_9 = button_syllable.clone();
+        button.connect_clicked(move |_| {
+            let mut state = shared_state_clone_9.lock().unwrap();
+            if let Some(syllable) = &state.syllable {
+                let written = syllable.written();
+                state.answer(&written, None, knew);
+                drop(state);
+                button_syllable_9.clicked();
+                return;
+            }
+
+            let letter = letters_rc_9.lock().unwrap()[state.curr_index]
+                .letter
+                .clone();
+            state.answer(&letter, None, knew);
+
+            if state.leitner_mode {
+                if knew {
+                    state.leitner.promote(&letter);
+                } else {
+                    state.leitner.demote(&letter);
+                }
+                drop(state);
+                button_next_9.clicked();
+            }
+        });
+    }
+
+    let letters_rc_12 = letters_rc.clone();
+    let shared_state_clone_12 = Arc::clone(&shared_state);
+    let box_indicator_12 = box_indicator.clone();
+    let button_next_12 = button_next.clone();
+    check_leitner.connect_toggled(move |check| {
+        shared_state_clone_12.lock().unwrap().leitner_mode = check.is_active();
+        if check.is_active() {
+            update_boxes(
+                &shared_state_clone_12.lock().unwrap(),
+                &letters_rc_12.lock().unwrap(),
+                &box_indicator_12,
+            );
+            box_indicator_12.widget.show();
+            button_next_12.clicked();
+        } else {
+            box_indicator_12.widget.hide();
+        }
+    });
+
+    // A new cycle starts whenever the shuffle mode is switched on, the seed
+    // is edited or Reshuffle is pressed.
+    let start_cycle = {
+        let letters_rc_13 = letters_rc.clone();
+        let shared_state_clone_13 = Arc::clone(&shared_state);
+        let label_round_13 = label_round.clone();
+        let button_random_13 = button_random.clone();
+        move |seed: u64| {
+            let indices = shared_state_clone_13
+                .lock()
+                .unwrap()
+                .indices(&letters_rc_13.lock().unwrap());
+            let cycle = ShuffledCycle::new(seed, &indices);
+            label_round_13.set_text(&cycle.progress());
+            shared_state_clone_13.lock().unwrap().shuffle = Some(cycle);
+            button_random_13.clicked();
+        }
+    };
+
+    let shared_state_clone_14 = Arc::clone(&shared_state);
+    let entry_seed_14 = entry_seed.clone();
+    let h_box_cycle_14 = h_box_cycle.clone();
+    let start_cycle_14 = start_cycle.clone();
+    check_shuffle.connect_toggled(move |check| {
+        if check.is_active() {
+            let seed = ShuffledCycle::random_seed();
+            entry_seed_14.set_text(&seed.to_string());
+            start_cycle_14(seed);
+            h_box_cycle_14.show();
+        } else {
+            shared_state_clone_14.lock().unwrap().shuffle = None;
+            h_box_cycle_14.hide();
+        }
+    });
+
+    let entry_seed_15 = entry_seed.clone();
+    let start_cycle_15 = start_cycle.clone();
+    button_reshuffle.connect_clicked(move |_| {
+        let seed = ShuffledCycle::random_seed();
+        entry_seed_15.set_text(&seed.to_string());
+        start_cycle_15(seed);
+    });
+
+    let shared_state_clone_16 = Arc::clone(&shared_state);
+    entry_seed.connect_activate(move |entry| match entry.text().trim().parse::<u64>() {
+        Ok(seed) => start_cycle(seed),
+        Err(_) => {
+            if let Some(cycle) = shared_state_clone_16.lock().unwrap().shuffle.as_ref() {
+                entry.set_text(&cycle.seed().to_string());
+            }
+        }
+    });
+
+    let letters_rc_17 = letters_rc.clone();
+    let shared_state_clone_17 = Arc::clone(&shared_state);
+    let label_1_rc_17 = label_1_rc.clone();
+    let label_3_rc_17 = label_3_rc.clone();
+    let label_4_rc_17 = label_4_rc.clone();
+    let keyboard_17 = keyboard.clone();
+    let box_indicator_17 = box_indicator.clone();
+    let label_round_17 = label_round.clone();
+    combo_deck.connect_changed(move |combo| {
+        // Refilling the list briefly leaves nothing selected.
+        let name = match combo.active_id() {
+            Some(name) => name,
+            None => return,
+        };
+        let binding = letters_rc_17.lock().unwrap();
+        let deck = match deck::all_decks(&binding)
+            .into_iter()
+            .find(|d| d.name == name)
+        {
+            Some(deck) => deck,
+            None => return,
+        };
+
+        let mut state = shared_state_clone_17.lock().unwrap();
+        if state.deck.name == deck.name && state.deck.indices == deck.indices {
+            return;
+        }
+        state.deck = deck;
+        state.syllable = None;
+        let indices = state.indices(&binding);
+        state.curr_index = indices[0];
+        events::emit(Event::CardShown);
+        if let Some(cycle) = state.shuffle.as_mut() {
+            *cycle = ShuffledCycle::new(cycle.seed(), &indices);
+            label_round_17.set_text(&cycle.progress());
+        }
+
+        compose_view(
+            &binding.get(state.curr_index).unwrap(),
+            &label_1_rc_17,
+            &label_3_rc_17,
+            &label_4_rc_17,
+            &keyboard_17,
+        );
+        update_boxes(&state, &binding, &box_indicator_17);
+    });
+
+    let letters_rc_18 = letters_rc.clone();
+    let app_18 = app.clone();
+    let combo_deck_18 = combo_deck.clone();
+    button_sets.connect_clicked(move |_| {
+        let letters_rc_19 = letters_rc_18.clone();
+        let combo_deck_19 = combo_deck_18.clone();
+        study_sets::build_study_sets_window(&app_18, letters_rc_18.clone(), move || {
+            // Refilling selects a deck, whose handler locks the letters too.
+            let names = deck_names(&letters_rc_19.lock().unwrap());
+            fill_deck_combo(&combo_deck_19, &names);
+        });
+    });
+
+    let letters_rc_5 = letters_rc.clone();
+    let shared_state_clone_5 = Arc::clone(&shared_state);
+    let app_5 = app.clone();
+    button_typing.connect_clicked(move |_| {
+        typing::build_typing_window(&app_5, letters_rc_5.clone(), shared_state_clone_5.clone());
+    });
+
+    let letters_rc_6 = letters_rc.clone();
+    let shared_state_clone_6 = Arc::clone(&shared_state);
+    let app_6 = app.clone();
+    button_classes.connect_clicked(move |_| {
+        class_sort::build_class_sort_window(
+            &app_6,
+            letters_rc_6.clone(),
+            shared_state_clone_6.clone(),
         );
     });
 
+    let letters_rc_7 = letters_rc.clone();
+    let shared_state_clone_7 = Arc::clone(&shared_state);
+    let app_7 = app.clone();
+    button_tones.connect_clicked(move |_| {
+        tones::build_tone_window(&app_7, letters_rc_7.clone(), shared_state_clone_7.clone());
+    });
+
+    let letters_rc_8 = letters_rc.clone();
+    let shared_state_clone_8 = Arc::clone(&shared_state);
+    let app_8 = app.clone();
+    button_speed.connect_clicked(move |_| {
+        speed_round::build_speed_round_window(
+            &app_8,
+            letters_rc_8.clone(),
+            shared_state_clone_8.clone(),
+        );
+    });
+
+    let letters_rc_20 = letters_rc.clone();
+    let shared_state_clone_20 = Arc::clone(&shared_state);
+    let app_20 = app.clone();
+    button_confusables.connect_clicked(move |_| {
+        confusables::build_confusables_window(
+            &app_20,
+            letters_rc_20.clone(),
+            shared_state_clone_20.clone(),
+        );
+    });
+
+    let letters_rc_22 = letters_rc.clone();
+    let shared_state_clone_22 = Arc::clone(&shared_state);
+    let app_22 = app.clone();
+    button_dictation.connect_clicked(move |_| {
+        dictation::build_dictation_window(
+            &app_22,
+            letters_rc_22.clone(),
+            shared_state_clone_22.clone(),
+        );
+    });
+
+    let letters_rc_23 = letters_rc.clone();
+    let shared_state_clone_23 = Arc::clone(&shared_state);
+    let app_23 = app.clone();
+    button_memory.connect_clicked(move |_| {
+        memory::build_memory_window(
+            &app_23,
+            letters_rc_23.clone(),
+            shared_state_clone_23.clone(),
+        );
+    });
+
+    let letters_rc_24 = letters_rc.clone();
+    let shared_state_clone_24 = Arc::clone(&shared_state);
+    let app_24 = app.clone();
+    button_exam.connect_clicked(move |_| {
+        exam::build_exam_window(
+            &app_24,
+            letters_rc_24.clone(),
+            shared_state_clone_24.clone(),
+        );
+    });
+
+    let shared_state_clone_10 = Arc::clone(&shared_state);
+    let app_10 = app.clone();
+    button_end.connect_clicked(move |_| {
+        end_session(&app_10, &shared_state_clone_10);
+    });
+
+    let shared_state_clone_11 = Arc::clone(&shared_state);
+    let app_11 = app.clone();
+    window.connect_delete_event(move |_, _| {
+        end_session(&app_11, &shared_state_clone_11);
+        gtk::glib::Propagation::Proceed
+    });
+
     let current_index = 0;
     let letters_rc_0 = letters_rc.clone();
+    let names = deck_names(&letters_rc_0.lock().unwrap());
+    fill_deck_combo(&combo_deck, &names);
     let binding = letters_rc_0.lock().unwrap();
     let label_1_rc_0 = label_1_rc.clone();
     let label_3_rc_0 = label_3_rc.clone();
@@ -218,13 +855,17 @@ fn build_ui(app: &gtk::Application, shared_state: Arc<Mutex<Context>>) -> Applic
         &label_1_rc_0,
         &label_3_rc_0,
         &label_4_rc_0,
+        &keyboard,
     );
+    events::emit(Event::CardShown);
 
     window.add(&v_box);
 
     window.show_all();
     label_3_rc_0.hide();
     label_4_rc_0.hide();
+    box_indicator.widget.hide();
+    h_box_cycle.hide();
 
     return window;
 }
@@ -234,7 +875,20 @@ fn main() {
         .application_id("com.example.learn-thai")
         .build();
 
-    let shared_state = Arc::new(Mutex::new(Context { curr_index: 0 }));
+    let shared_state = Arc::new(Mutex::new(Context {
+        curr_index: 0,
+        deck: deck::builtin_decks(&get_letters()).remove(0),
+        session: Session::default(),
+        leitner: Leitner::load(),
+        goals: Goals::load(),
+        unlock: Unlock::load(),
+        audio: Audio::start(),
+        manifest: Manifest::default(),
+        settings: Settings::load(),
+        syllable: None,
+        leitner_mode: false,
+        shuffle: None,
+    }));
 
     application.connect_activate(move |app| {
         build_ui(app, shared_state.clone());
@@ -244,30 +898,30 @@ fn main() {
 }
 
 impl Letter {
-    fn play_letter(&self) {
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-        let path = format!(
-            "audio/{}.mp3",
-            if self.consonant {
-                self.example.clone().unwrap() // consonants group always have an example
-            } else {
-                self.letter.clone()
-            }
-        );
+    /// The word after the letter name in `example`, e.g. "ไก่" for "ก ไก่".
+    fn example_word(&self) -> Option<&str> {
+        self.example
+            .as_ref()
+            .and_then(|e| e.split_once(' '))
+            .map(|(_, word)| word)
+    }
 
-        match File::open(path) {
-            Ok(f) => {
-                let file = BufReader::new(f);
-                let source = Decoder::new(file).unwrap();
-                let sink = Sink::try_new(&stream_handle).unwrap();
+    /// The characters actually written for this entry; vowels are listed on
+    /// a placeholder อ, which is dropped.
+    fn glyphs(&self) -> String {
+        if self.consonant {
+            self.letter.clone()
+        } else {
+            self.letter.replacen('อ', "", 1)
+        }
+    }
 
-                sink.append(source);
-                sink.sleep_until_end();
-            }
-            Err(e) => {
-                println!("Can't open audio file: {}", e)
-            }
-        };
+    fn class(&self) -> Option<ConsonantClass> {
+        if self.consonant {
+            self.letter.chars().next().and_then(thai::consonant_class)
+        } else {
+            None
+        }
     }
 }
 
@@ -0,0 +1,207 @@
+use crate::events::{self, Event};
+use crate::paths;
+use crate::record;
+use crate::speech::MANIFEST;
+use crate::Context;
+use gtk::prelude::*;
+use gtk::Button;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const MANIFEST_FILE: &str = "manifest.tsv";
+
+/// What a clip says about an entry.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ClipKind {
+    /// The letter's name, e.g. "ก ไก่".
+    Name,
+    /// The sound alone, as for vowels.
+    Sound,
+    Example,
+    /// Any of the others said slowly.
+    Slow,
+}
+
+impl ClipKind {
+    fn from_id(id: &str) -> Option<ClipKind> {
+        match id {
+            "name" => Some(ClipKind::Name),
+            "sound" => Some(ClipKind::Sound),
+            "example" => Some(ClipKind::Example),
+            "slow" => Some(ClipKind::Slow),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClipKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match self {
+            ClipKind::Name => "Name",
+            ClipKind::Sound => "Sound",
+            ClipKind::Example => "Example",
+            ClipKind::Slow => "Slow",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Clone)]
+pub struct Clip {
+    pub kind: ClipKind,
+    /// File name of the clip, e.g. "ก ไก่.mp3", looked up like any other
+    /// recording.
+    pub file: String,
+}
+
+/// The clips of each deck entry, from `manifest.tsv` files with one
+/// `entry<TAB>kind<TAB>file` record per clip, in the order they are listed.
+#[derive(Clone, Default)]
+pub struct Manifest {
+    entries: HashMap<String, Vec<Clip>>,
+}
+
+impl Manifest {
+    /// The built-in manifest, overridden entry by entry by those of the
+    /// installed assets and then by the one in `folder`.
+    pub fn load(folder: Option<&Path>) -> Manifest {
+        let mut manifest = Manifest::default();
+        manifest.add(MANIFEST);
+
+        let mut files: Vec<_> = paths::asset_dirs()
+            .into_iter()
+            .rev()
+            .map(|dir| dir.join("audio").join(MANIFEST_FILE))
+            .collect();
+        files.extend(folder.map(|f| f.join(MANIFEST_FILE)));
+        for file in files {
+            if let Ok(content) = fs::read_to_string(&file) {
+                manifest.add(&content);
+            }
+        }
+        manifest
+    }
+
+    /// Entries listed in `content` replace all earlier clips of the entry.
+    fn add(&mut self, content: &str) {
+        let mut listed: HashMap<String, Vec<Clip>> = HashMap::new();
+        for line in content.lines().filter(|line| !line.is_empty()) {
+            let record: Vec<&str> = line.split('\t').collect();
+            match record.as_slice() {
+                [entry, kind, file] => match ClipKind::from_id(kind) {
+                    Some(kind) => listed.entry(entry.to_string()).or_default().push(Clip {
+                        kind,
+                        file: file.to_string(),
+                    }),
+                    None => println!("Unknown clip kind {} for {}", kind, entry),
+                },
+                _ => println!("Can't read manifest line: {}", line),
+            }
+        }
+        self.entries.extend(listed);
+    }
+
+    pub fn clips(&self, entry: &str) -> &[Clip] {
+        self.entries
+            .get(entry)
+            .map_or(&[], |clips| clips.as_slice())
+    }
+
+    /// The first clip of the given kind, falling back to the main clip.
+    pub fn clip_of_kind(&self, entry: &str, kind: ClipKind) -> String {
+        self.clips(entry)
+            .iter()
+            .find(|clip| clip.kind == kind)
+            .map_or_else(|| self.main_clip(entry), |clip| clip.file.clone())
+    }
+
+    /// What to play for an entry: its first clip, or the entry itself, which
+    /// is spoken if nothing is recorded under that name.
+    pub fn main_clip(&self, entry: &str) -> String {
+        self.clips(entry)
+            .first()
+            .map_or_else(|| entry.to_string(), |clip| clip.file.clone())
+    }
+}
+
+/// A play button for each clip of the entry on screen. `entries` are those
+/// of the letters, by index.
+pub fn build_clip_buttons(entries: Vec<String>, shared_state: Arc<Mutex<Context>>) -> gtk::Box {
+    let h_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+
+    let h_box_1 = h_box.clone();
+    events::subscribe(move |event| {
+        if !matches!(event, Event::CardShown | Event::AudioChanged) {
+            return;
+        }
+        let (entry, clips) = {
+            let state = shared_state.lock().unwrap();
+            let entry = record::current_entry(&state, &entries);
+            let clips = state.manifest.clips(&entry).to_vec();
+            (entry, clips)
+        };
+        // Without clips the entry is still played, recorded or spoken.
+        let buttons: Vec<(String, String)> = if clips.is_empty() {
+            vec![(String::from("Play"), entry)]
+        } else {
+            clips
+                .iter()
+                .map(|clip| (clip.kind.to_string(), clip.file.clone()))
+                .collect()
+        };
+
+        for child in h_box_1.children() {
+            h_box_1.remove(&child);
+        }
+        for (label, file) in buttons {
+            let button = Button::with_label(&label);
+            button.set_tooltip_text(Some(&file));
+            let shared_state_1 = shared_state.clone();
+            button.connect_clicked(move |_| {
+                shared_state_1.lock().unwrap().audio.play(&file);
+            });
+            h_box_1.pack_start(&button, false, false, 0);
+        }
+        h_box_1.show_all();
+    });
+
+    h_box
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn several_clips_per_entry() {
+        let mut manifest = Manifest::default();
+        manifest.add("ก\tname\tก ไก่.mp3\nก\texample\tไก่.mp3\nก\tslow\tก ไก่ slow.mp3\n");
+        let kinds: Vec<ClipKind> = manifest.clips("ก").iter().map(|c| c.kind).collect();
+        assert!(kinds == [ClipKind::Name, ClipKind::Example, ClipKind::Slow]);
+        assert_eq!(manifest.main_clip("ก"), "ก ไก่.mp3");
+        assert_eq!(manifest.clip_of_kind("ก", ClipKind::Slow), "ก ไก่ slow.mp3");
+        assert_eq!(manifest.clip_of_kind("ก", ClipKind::Sound), "ก ไก่.mp3");
+        assert_eq!(manifest.main_clip("ข"), "ข");
+    }
+
+    #[test]
+    fn later_manifest_replaces_listed_entries() {
+        let mut manifest = Manifest::default();
+        manifest.add("ก\tname\tก ไก่.mp3\nข\tname\tข ไข่.mp3\n");
+        manifest.add("ก\tslow\tslow.mp3\nก\tbogus\tx.mp3\nbroken line\n");
+        assert_eq!(manifest.clips("ก").len(), 1);
+        assert_eq!(manifest.main_clip("ก"), "slow.mp3");
+        assert_eq!(manifest.main_clip("ข"), "ข ไข่.mp3");
+    }
+
+    #[test]
+    fn built_in_manifest_loads() {
+        let mut manifest = Manifest::default();
+        manifest.add(MANIFEST);
+        assert_eq!(manifest.entries.len(), MANIFEST.lines().count());
+        assert!(manifest.entries.values().all(|clips| clips.len() == 1));
+    }
+}
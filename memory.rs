@@ -0,0 +1,287 @@
+use crate::{Context, Letter};
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, Button, ComboBoxText, Grid, Label};
+use pango::glib::{markup_escape_text, timeout_add_local, ControlFlow};
+use rand::seq::SliceRandom;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long two cards that don't match stay face up.
+const FLIP_BACK: Duration = Duration::from_millis(900);
+/// (rows, columns) of the grids to choose from.
+const GRID_SIZES: [(i32, i32); 4] = [(3, 4), (4, 4), (4, 5), (5, 6)];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Match {
+    Romanization,
+    Meaning,
+    Audio,
+}
+
+impl Match {
+    fn from_id(id: &str) -> Match {
+        match id {
+            "meaning" => Match::Meaning,
+            "audio" => Match::Audio,
+            _ => Match::Romanization,
+        }
+    }
+
+    /// What the partner card of `l` shows, if `l` can be used at all.
+    fn partner(&self, l: &Letter) -> Option<String> {
+        match self {
+            Match::Romanization => Some(l.english_letter.clone()),
+            Match::Meaning => l.example_meaning.clone(),
+            Match::Audio => Some(String::from("♪")),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Card {
+    letter: usize,
+    /// Whether this is the Thai side of the pair or its partner.
+    thai: bool,
+    text: String,
+}
+
+struct Memory {
+    mode: Match,
+    cards: Vec<Card>,
+    face_up: Vec<bool>,
+    matched: Vec<bool>,
+    open: Vec<usize>,
+    moves: usize,
+    pairs: usize,
+}
+
+impl Memory {
+    fn new(mode: Match, pairs: usize, letters: &[Letter], unlocked: &[usize]) -> Memory {
+        let mut indices = unlocked.to_vec();
+        indices.shuffle(&mut rand::thread_rng());
+
+        // Partners must differ, or two pairs could not be told apart.
+        let mut used = HashSet::new();
+        let mut cards = Vec::new();
+        for i in indices {
+            if cards.len() == pairs * 2 {
+                break;
+            }
+            let partner = match mode.partner(&letters[i]) {
+                Some(partner) => partner,
+                None => continue,
+            };
+            if mode != Match::Audio && !used.insert(partner.clone()) {
+                continue;
+            }
+            cards.push(Card {
+                letter: i,
+                thai: true,
+                text: letters[i].letter.clone(),
+            });
+            cards.push(Card {
+                letter: i,
+                thai: false,
+                text: partner,
+            });
+        }
+        cards.shuffle(&mut rand::thread_rng());
+
+        Memory {
+            mode,
+            face_up: vec![false; cards.len()],
+            matched: vec![false; cards.len()],
+            cards,
+            open: Vec::new(),
+            moves: 0,
+            pairs: 0,
+        }
+    }
+
+    fn stats(&self) -> String {
+        if self.pairs * 2 == self.cards.len() {
+            return format!("All pairs found in {} moves", self.moves);
+        }
+        format!(
+            "Moves: {}   Pairs: {} / {}",
+            self.moves,
+            self.pairs,
+            self.cards.len() / 2
+        )
+    }
+}
+
+fn show_card(game: &Memory, i: usize, label: &Label) {
+    if !game.face_up[i] {
+        label.set_markup("<span font_desc='Normal 30'>?</span>");
+        return;
+    }
+    let card = &game.cards[i];
+    let size = if card.thai { 30 } else { 14 };
+    let color = if game.matched[i] {
+        " foreground='#27ae60'"
+    } else {
+        ""
+    };
+    label.set_markup(&format!(
+        "<span font_desc='Noto Looped Thai UI Normal {}'{}>{}</span>",
+        size,
+        color,
+        markup_escape_text(&card.text)
+    ));
+}
+
+pub fn build_memory_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Memory")
+        .default_width(700)
+        .default_height(600)
+        .build();
+
+    let combo_mode = ComboBoxText::new();
+    combo_mode.append(Some("romanization"), "Letter and romanization");
+    combo_mode.append(Some("meaning"), "Letter and example meaning");
+    combo_mode.append(Some("audio"), "Letter and sound");
+    combo_mode.set_active_id(Some("romanization"));
+    let combo_size = ComboBoxText::new();
+    for (rows, columns) in GRID_SIZES {
+        let id = format!("{}x{}", rows, columns);
+        combo_size.append(Some(&id), &id);
+    }
+    combo_size.set_active(Some(1));
+    let button_new = Button::with_label("New game");
+    let label_stats = Label::new(None);
+    let grid = Grid::new();
+    grid.set_row_spacing(6);
+    grid.set_column_spacing(6);
+    grid.set_row_homogeneous(true);
+    grid.set_column_homogeneous(true);
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_settings = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box_settings.pack_start(&combo_mode, true, false, 0);
+    h_box_settings.pack_start(&combo_size, true, false, 0);
+    h_box_settings.pack_start(&button_new, true, false, 0);
+    v_box.pack_start(&h_box_settings, false, false, 0);
+    v_box.pack_start(&grid, true, true, 0);
+    v_box.pack_start(&label_stats, false, false, 0);
+
+    let game: Rc<RefCell<Option<Memory>>> = Rc::new(RefCell::new(None));
+    let labels: Rc<RefCell<Vec<Label>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let new_game = {
+        let combo_mode = combo_mode.clone();
+        let combo_size = combo_size.clone();
+        let label_stats = label_stats.clone();
+        let shared_state = shared_state.clone();
+        move || {
+            let mode = Match::from_id(&combo_mode.active_id().unwrap_or_default());
+            let (rows, columns) = GRID_SIZES[combo_size.active().unwrap_or(0) as usize];
+            let binding = letters.lock().unwrap();
+            let unlocked = shared_state.lock().unwrap().unlock.unlocked(&binding);
+            let g = Memory::new(mode, (rows * columns / 2) as usize, &binding, &unlocked);
+            drop(binding);
+
+            for child in grid.children() {
+                grid.remove(&child);
+            }
+            labels.borrow_mut().clear();
+            for i in 0..g.cards.len() {
+                let label = Label::new(None);
+                let button = Button::new();
+                button.add(&label);
+                show_card(&g, i, &label);
+                grid.attach(&button, i as i32 % columns, i as i32 / columns, 1, 1);
+                labels.borrow_mut().push(label);
+
+                let game_1 = game.clone();
+                let labels_1 = labels.clone();
+                let letters_1 = letters.clone();
+                let shared_state_1 = shared_state.clone();
+                let label_stats_1 = label_stats.clone();
+                button.connect_clicked(move |_| {
+                    let mut binding = game_1.borrow_mut();
+                    let g = binding.as_mut().unwrap();
+                    if g.open.len() == 2 || g.face_up[i] {
+                        return;
+                    }
+                    g.face_up[i] = true;
+                    g.open.push(i);
+                    show_card(g, i, &labels_1.borrow()[i]);
+                    if g.mode == Match::Audio && !g.cards[i].thai {
+                        let entry = letters_1.lock().unwrap()[g.cards[i].letter].letter.clone();
+                        let state = shared_state_1.lock().unwrap();
+                        state.audio.play(&state.manifest.main_clip(&entry));
+                    }
+                    if g.open.len() < 2 {
+                        return;
+                    }
+
+                    g.moves += 1;
+                    let (a, b) = (g.open[0], g.open[1]);
+                    let matched = g.cards[a].letter == g.cards[b].letter;
+                    {
+                        // A letter turned up with another's partner is a
+                        // confusion between the two.
+                        let binding = letters_1.lock().unwrap();
+                        let expected = &binding[g.cards[a].letter].letter;
+                        let given = Some(&binding[g.cards[b].letter].letter)
+                            .filter(|_| !matched && g.cards[a].thai != g.cards[b].thai);
+                        let mut state = shared_state_1.lock().unwrap();
+                        state.answer(expected, given.map(String::as_str), matched);
+                        state.goals.study();
+                    }
+                    if matched {
+                        g.matched[a] = true;
+                        g.matched[b] = true;
+                        g.open.clear();
+                        g.pairs += 1;
+                        show_card(g, a, &labels_1.borrow()[a]);
+                        show_card(g, b, &labels_1.borrow()[b]);
+                        label_stats_1.set_text(&g.stats());
+                        return;
+                    }
+                    label_stats_1.set_text(&g.stats());
+
+                    let game_2 = game_1.clone();
+                    let labels_2 = labels_1.clone();
+                    timeout_add_local(FLIP_BACK, move || {
+                        // A new game may have been started in the meantime.
+                        if let Some(g) = game_2.borrow_mut().as_mut().filter(|g| g.open == [a, b]) {
+                            g.open.clear();
+                            for i in [a, b] {
+                                g.face_up[i] = false;
+                                show_card(g, i, &labels_2.borrow()[i]);
+                            }
+                        }
+                        ControlFlow::Break
+                    });
+                });
+            }
+
+            label_stats.set_text(&g.stats());
+            *game.borrow_mut() = Some(g);
+            grid.show_all();
+        }
+    };
+    new_game();
+
+    let new_game_1 = new_game.clone();
+    combo_mode.connect_changed(move |_| new_game_1());
+    let new_game_2 = new_game.clone();
+    combo_size.connect_changed(move |_| new_game_2());
+    button_new.connect_clicked(move |_| new_game());
+
+    window.add(&v_box);
+    window.show_all();
+
+    window
+}
@@ -0,0 +1,70 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Name of the app's folder in the XDG directories.
+const APP_DIR: &str = "thai";
+/// Environment variable naming an extra folder searched for assets first.
+const ASSETS_ENV: &str = "THAI_ASSETS";
+
+fn home() -> PathBuf {
+    env::var_os("HOME").map(PathBuf::from).unwrap_or_default()
+}
+
+/// An absolute path from the environment variable, or `fallback` under the
+/// home directory when it is unset or relative, as the XDG spec asks.
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match env::var_os(var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => home().join(fallback),
+    }
+}
+
+/// Where progress, scores and study sets are kept.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join(APP_DIR)
+}
+
+/// Where settings are kept.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join(APP_DIR)
+}
+
+/// Folders searched for assets such as recordings, most important first:
+/// the `THAI_ASSETS` override, the user's data folder, the system data
+/// folders and the folder of the executable.
+pub fn asset_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os(ASSETS_ENV) {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs.push(data_dir());
+
+    let system = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+    dirs.extend(
+        system
+            .split(':')
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .map(|dir| dir.join(APP_DIR)),
+    );
+
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+    {
+        dirs.push(dir);
+    }
+    dirs
+}
+
+/// The first asset at `relative`, e.g. "audio/ก ไก่.mp3", in the asset
+/// folders.
+pub fn find_asset(relative: &str) -> Option<PathBuf> {
+    asset_dirs()
+        .into_iter()
+        .map(|dir| dir.join(relative))
+        .find(|path| path.is_file())
+}
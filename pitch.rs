@@ -0,0 +1,286 @@
+use crate::events::{self, Event};
+use crate::record;
+use crate::speech;
+use crate::tone_score;
+use crate::Context;
+use gtk::cairo::Context as Cairo;
+use gtk::gio;
+use gtk::glib::{self, Propagation};
+use gtk::prelude::*;
+use gtk::{DrawingArea, Label};
+use rodio::{Decoder, Source};
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// Pitch is tracked every this many seconds.
+const HOP_SECS: f32 = 0.01;
+/// Length of the window a pitch is measured over.
+const WINDOW_SECS: f32 = 0.03;
+/// Range of speaking voices, low men to high children.
+const MIN_F0: f32 = 70.0;
+const MAX_F0: f32 = 500.0;
+/// YIN threshold on the normalized difference; lower is stricter.
+const THRESHOLD: f32 = 0.15;
+/// Frames quieter than this share of the loudest frame count as silence.
+const SILENCE: f32 = 0.05;
+/// Audio is averaged down to about this rate before tracking.
+const TRACK_RATE: u32 = 11025;
+
+/// A pitch in Hz every `HOP_SECS`, None where there is no voice.
+pub type Contour = Vec<Option<f32>>;
+
+/// Decodes audio data to mono samples and their rate.
+pub fn decode_mono(data: Vec<u8>) -> Option<(Vec<f32>, u32)> {
+    let source = Decoder::new(Cursor::new(data)).ok()?;
+    let channels = source.channels().max(1) as usize;
+    let sample_rate = source.sample_rate();
+    let samples: Vec<f32> = source.convert_samples().collect();
+    let mono = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    Some((mono, sample_rate))
+}
+
+/// Tracks the fundamental frequency of mono samples with YIN.
+pub fn contour(samples: &[f32], sample_rate: u32) -> Contour {
+    // Tones live well below a few hundred Hz, so a lower rate loses nothing.
+    let factor = (sample_rate / TRACK_RATE).max(1) as usize;
+    let samples: Vec<f32> = samples
+        .chunks(factor)
+        .map(|c| c.iter().sum::<f32>() / c.len() as f32)
+        .collect();
+    let rate = sample_rate as f32 / factor as f32;
+
+    let window = (rate * WINDOW_SECS) as usize;
+    let hop = (rate * HOP_SECS) as usize;
+    let tau_min = (rate / MAX_F0) as usize;
+    let tau_max = (rate / MIN_F0) as usize;
+    if samples.len() < window + tau_max + 1 {
+        return Vec::new();
+    }
+
+    let starts: Vec<usize> = (0..samples.len() - window - tau_max).step_by(hop).collect();
+    let loudness: Vec<f32> = starts
+        .iter()
+        .map(|&s| {
+            (samples[s..s + window].iter().map(|x| x * x).sum::<f32>() / window as f32).sqrt()
+        })
+        .collect();
+    let loudest = loudness.iter().cloned().fold(0.0, f32::max);
+
+    starts
+        .iter()
+        .zip(&loudness)
+        .map(|(&start, &loud)| {
+            if loud < loudest * SILENCE {
+                return None;
+            }
+            yin_period(
+                &samples[start..start + window + tau_max],
+                window,
+                tau_min,
+                tau_max,
+            )
+            .map(|period| rate / period)
+        })
+        .collect()
+}
+
+/// The period in samples of the frame at the start of `frame`, if voiced.
+fn yin_period(frame: &[f32], window: usize, tau_min: usize, tau_max: usize) -> Option<f32> {
+    // Cumulative mean normalized difference; index 0 is 1 by definition.
+    let mut normalized = vec![1.0; tau_max + 1];
+    let mut running = 0.0;
+    for (tau, value) in normalized.iter_mut().enumerate().skip(1) {
+        let difference: f32 = (0..window)
+            .map(|j| (frame[j] - frame[j + tau]).powi(2))
+            .sum();
+        running += difference;
+        *value = if running > 0.0 {
+            difference * tau as f32 / running
+        } else {
+            1.0
+        };
+    }
+
+    let mut tau = tau_min;
+    while tau < tau_max {
+        if normalized[tau] < THRESHOLD {
+            while tau + 1 < tau_max && normalized[tau + 1] < normalized[tau] {
+                tau += 1;
+            }
+            break;
+        }
+        tau += 1;
+    }
+    if tau >= tau_max {
+        return None;
+    }
+
+    // A parabola through the neighbours finds the dip between samples.
+    let (a, b, c) = (normalized[tau - 1], normalized[tau], normalized[tau + 1]);
+    let bend = a - 2.0 * b + c;
+    let shift = if bend.abs() > f32::EPSILON {
+        (a - c) / (2.0 * bend)
+    } else {
+        0.0
+    };
+    Some(tau as f32 + shift.clamp(-1.0, 1.0))
+}
+
+/// The voiced part of a contour, without silence before and after.
+fn trimmed(contour: &Contour) -> &[Option<f32>] {
+    let first = contour.iter().position(|p| p.is_some()).unwrap_or(0);
+    let last = contour
+        .iter()
+        .rposition(|p| p.is_some())
+        .map_or(0, |i| i + 1);
+    &contour[first..last.max(first)]
+}
+
+/// Draws each contour as a line, stretched to the same width so their
+/// shapes can be compared. Heights are semitones over a shared range.
+fn draw_contours(cr: &Cairo, width: f64, height: f64, contours: &[(&Contour, (f64, f64, f64))]) {
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.paint().unwrap();
+
+    let semitones = |f0: f32| 12.0 * (f0 as f64).log2();
+    let pitches: Vec<f64> = contours
+        .iter()
+        .flat_map(|(c, _)| c.iter().flatten().map(|f| semitones(*f)))
+        .collect();
+    if pitches.is_empty() {
+        return;
+    }
+    // A little room above and below, and at least an octave so small
+    // wobbles don't look like tones.
+    let low = pitches.iter().cloned().fold(f64::MAX, f64::min);
+    let high = pitches.iter().cloned().fold(f64::MIN, f64::max);
+    let middle = (low + high) / 2.0;
+    let span = (high - low).max(12.0) + 2.0;
+    let y_of = |st: f64| height * (0.5 - (st - middle) / span);
+
+    cr.set_line_width(3.0);
+    for (contour, (r, g, b)) in contours {
+        let voiced = trimmed(contour);
+        if voiced.len() < 2 {
+            continue;
+        }
+        cr.set_source_rgb(*r, *g, *b);
+        let step = width / (voiced.len() - 1) as f64;
+        let mut drawing = false;
+        for (i, pitch) in voiced.iter().enumerate() {
+            match pitch {
+                Some(f0) => {
+                    let (x, y) = (i as f64 * step, y_of(semitones(*f0)));
+                    if drawing {
+                        cr.line_to(x, y);
+                    } else {
+                        cr.move_to(x, y);
+                    }
+                    drawing = true;
+                }
+                None => drawing = false,
+            }
+        }
+        cr.stroke().unwrap();
+    }
+}
+
+/// Contour of the reference `clip` and that of the learner's latest
+/// recording of `entry`.
+fn contours_of(entry: &str, clip: &str, folder: Option<PathBuf>) -> (Contour, Contour) {
+    let reference = speech::backends(folder)
+        .iter()
+        .find_map(|backend| backend.speak(clip))
+        .and_then(decode_mono)
+        .map(|(samples, rate)| contour(&samples, rate))
+        .unwrap_or_default();
+    let own = record::recordings(entry)
+        .pop()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(decode_mono)
+        .map(|(samples, rate)| contour(&samples, rate))
+        .unwrap_or_default();
+    (reference, own)
+}
+
+/// Pitch of the reference recording in blue with the learner's own latest
+/// recording over it in orange, and how well its tone matches. `entries` are
+/// those of the letters, by index.
+pub fn build_pitch_view(entries: Vec<String>, shared_state: Arc<Mutex<Context>>) -> gtk::Box {
+    let area = DrawingArea::new();
+    area.set_size_request(-1, 80);
+    area.set_tooltip_text(Some(
+        "Pitch: reference in blue, your latest recording in orange",
+    ));
+
+    let contours = Rc::new(RefCell::new((Contour::new(), Contour::new())));
+    let contours_1 = contours.clone();
+    area.connect_draw(move |area, cr| {
+        let (reference, own) = &*contours_1.borrow();
+        draw_contours(
+            cr,
+            area.allocated_width() as f64,
+            area.allocated_height() as f64,
+            &[(reference, (0.2, 0.4, 0.8)), (own, (0.9, 0.5, 0.1))],
+        );
+        Propagation::Stop
+    });
+    let label_score = Label::new(None);
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    v_box.pack_start(&area, false, false, 0);
+    v_box.pack_start(&label_score, false, false, 0);
+
+    // Only the result of the latest request is shown, should an older one
+    // finish after it.
+    let latest = Rc::new(Cell::new(0u64));
+    events::subscribe(move |event| {
+        if !matches!(
+            event,
+            Event::CardShown | Event::Recorded | Event::AudioChanged
+        ) {
+            return;
+        }
+        let (entry, clip, folder, tone) = {
+            let state = shared_state.lock().unwrap();
+            let entry = record::current_entry(&state, &entries);
+            (
+                entry.clone(),
+                state.manifest.main_clip(&entry),
+                state.settings.audio_folder.clone(),
+                match &state.syllable {
+                    Some(syllable) => Some(syllable.tone()),
+                    None => tone_score::name_tone(&entry),
+                },
+            )
+        };
+        let request = latest.get() + 1;
+        latest.set(request);
+
+        // Speech, decoding and tracking take a while, so they run on a
+        // worker thread and the window stays responsive.
+        let latest_1 = latest.clone();
+        let contours_1 = contours.clone();
+        let label_score_1 = label_score.clone();
+        let area_1 = area.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let tracked = gio::spawn_blocking(move || contours_of(&entry, &clip, folder)).await;
+            let (reference, own) = match tracked {
+                Ok(tracked) if latest_1.get() == request => tracked,
+                _ => return,
+            };
+            label_score_1.set_text(&tone_score::report(&reference, &own, tone));
+            *contours_1.borrow_mut() = (reference, own);
+            area_1.queue_draw();
+        });
+    });
+
+    v_box
+}
@@ -0,0 +1,365 @@
+use crate::events::{self, Event};
+use crate::paths;
+use crate::Context;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::{Button, ComboBoxText, ToggleButton};
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample, Stream};
+use rodio::{Decoder, Source};
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// Environment variable naming an audio file to use in place of the
+/// microphone, so recording can be tried on machines without one.
+const INPUT_FILE_ENV: &str = "THAI_INPUT_FILE";
+
+/// Interleaved samples as heard by an input.
+pub struct Recording {
+    pub samples: Vec<f32>,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+/// Where the learner's voice comes from.
+pub trait Input {
+    fn start(&mut self) -> Result<(), String>;
+    /// Stops listening and hands over what was heard since `start`.
+    fn finish(&mut self) -> Result<Recording, String>;
+}
+
+/// The default input device of the system.
+pub struct Microphone {
+    stream: Option<Stream>,
+    samples: Arc<Mutex<Vec<f32>>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Microphone {
+    pub fn new() -> Microphone {
+        Microphone {
+            stream: None,
+            samples: Arc::new(Mutex::new(Vec::new())),
+            channels: 1,
+            sample_rate: 44100,
+        }
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Mutex<Vec<f32>>>,
+) -> Result<Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            samples
+                .lock()
+                .unwrap()
+                .extend(data.iter().map(|s| s.to_sample::<f32>()))
+        },
+        |e| println!("Microphone error: {}", e),
+        None,
+    )
+}
+
+impl Input for Microphone {
+    fn start(&mut self) -> Result<(), String> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("No microphone found")?;
+        let supported = device
+            .default_input_config()
+            .map_err(|e| format!("Can't use the microphone: {}", e))?;
+        let config = supported.config();
+        self.samples.lock().unwrap().clear();
+        let samples = self.samples.clone();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, samples),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, samples),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, samples),
+            format => return Err(format!("Unsupported microphone format {}", format)),
+        }
+        .map_err(|e| format!("Can't open the microphone: {}", e))?;
+        stream
+            .play()
+            .map_err(|e| format!("Can't start recording: {}", e))?;
+
+        self.channels = config.channels;
+        self.sample_rate = config.sample_rate.0;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Recording, String> {
+        // Dropping the stream stops it.
+        self.stream.take().ok_or("Not recording")?;
+        Ok(Recording {
+            samples: std::mem::take(&mut *self.samples.lock().unwrap()),
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+        })
+    }
+}
+
+/// Plays back the same audio file on every recording.
+pub struct FileInput {
+    pub path: PathBuf,
+}
+
+impl Input for FileInput {
+    fn start(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Recording, String> {
+        let file = File::open(&self.path)
+            .map_err(|e| format!("Can't open {}: {}", self.path.display(), e))?;
+        let source = Decoder::new(BufReader::new(file))
+            .map_err(|e| format!("Can't decode {}: {}", self.path.display(), e))?;
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        Ok(Recording {
+            samples: source.convert_samples().collect(),
+            channels,
+            sample_rate,
+        })
+    }
+}
+
+/// The microphone, or the file named by `THAI_INPUT_FILE` if it is set.
+pub fn default_input() -> Box<dyn Input> {
+    match env::var_os(INPUT_FILE_ENV) {
+        Some(path) => Box::new(FileInput {
+            path: PathBuf::from(path),
+        }),
+        None => Box::new(Microphone::new()),
+    }
+}
+
+/// Folder with the learner's recordings of one letter or syllable.
+fn folder_of(name: &str) -> PathBuf {
+    paths::data_dir().join("recordings").join(name)
+}
+
+/// The learner's recordings of `name`, oldest first.
+pub fn recordings(name: &str) -> Vec<PathBuf> {
+    recordings_in(&folder_of(name))
+}
+
+fn recordings_in(folder: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(folder) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "wav"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    // By stem, so a retake "… 10.250-2" comes after "… 10.250".
+    files.sort_by(|a, b| a.file_stem().cmp(&b.file_stem()));
+    files
+}
+
+/// Keeps a recording of `name`, named after the time it was made. Takes made
+/// within the same millisecond are numbered so none replaces another.
+pub fn save(name: &str, recording: &Recording) -> Option<PathBuf> {
+    save_in(&folder_of(name), recording)
+}
+
+fn save_in(folder: &Path, recording: &Recording) -> Option<PathBuf> {
+    if let Err(e) = fs::create_dir_all(folder) {
+        println!("Can't create {}: {}", folder.display(), e);
+        return None;
+    }
+    let stamp = glib::DateTime::now_local()
+        .and_then(|now| {
+            now.format("%Y-%m-%d %H-%M-%S")
+                .map(|d| format!("{}.{:03}", d, now.microsecond() / 1000))
+        })
+        .unwrap_or_default();
+    let mut path = folder.join(format!("{}.wav", stamp));
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = folder.join(format!("{}-{}.wav", stamp, n));
+    }
+    match write_wav(&path, recording) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            println!("Can't write {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Writes 16 bit PCM, which every player can read.
+fn write_wav(path: &Path, recording: &Recording) -> io::Result<()> {
+    let channels = recording.channels as u32;
+    let data_len = recording.samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&recording.channels.to_le_bytes());
+    bytes.extend_from_slice(&recording.sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(recording.sample_rate * channels * 2).to_le_bytes());
+    bytes.extend_from_slice(&(channels as u16 * 2).to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in &recording.samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    File::create(path)?.write_all(&bytes)
+}
+
+/// The entry on screen: a letter, or the syllable shown in its place.
+/// `entries` are those of the letters, by index.
+pub fn current_entry(state: &Context, entries: &[String]) -> String {
+    match &state.syllable {
+        Some(syllable) => syllable.written(),
+        None => entries[state.curr_index].clone(),
+    }
+}
+
+/// Lists the recordings of `name` by date, newest selected.
+fn fill_recordings(combo: &ComboBoxText, name: &str) {
+    let files = recordings(name);
+    combo.remove_all();
+    for path in &files {
+        let stamp = path.file_stem().unwrap().to_string_lossy().to_string();
+        combo.append(Some(&path.display().to_string()), &stamp);
+    }
+    combo.set_active(files.len().checked_sub(1).map(|i| i as u32));
+}
+
+/// Record, compare and replay buttons for what is on screen, with the takes
+/// kept per entry. `entries` are those of the letters, by index.
+pub fn build_recorder(entries: Vec<String>, shared_state: Arc<Mutex<Context>>) -> gtk::Box {
+    let button_record = ToggleButton::with_label("Record");
+    let button_compare = Button::with_label("A/B");
+    button_compare.set_tooltip_text(Some("Play the reference, then your latest recording"));
+    let combo_recordings = ComboBoxText::new();
+    combo_recordings.set_tooltip_text(Some("Your recordings"));
+    let button_play_mine = Button::with_label("Play mine");
+
+    let h_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    h_box.pack_start(&button_record, false, false, 0);
+    h_box.pack_start(&button_compare, false, false, 0);
+    h_box.pack_start(&combo_recordings, false, false, 0);
+    h_box.pack_start(&button_play_mine, false, false, 0);
+
+    let input = Rc::new(RefCell::new(default_input()));
+    let entries = Rc::new(entries);
+
+    let shared_state_3 = shared_state.clone();
+    let entries_3 = entries.clone();
+    // The card may change while recording; the take belongs to the first.
+    let recorded_name = RefCell::new(String::new());
+    button_record.connect_toggled(move |button| {
+        if button.is_active() {
+            *recorded_name.borrow_mut() =
+                current_entry(&shared_state_3.lock().unwrap(), &entries_3);
+            let started = input.borrow_mut().start();
+            if let Err(e) = started {
+                println!("{}", e);
+                button.set_active(false);
+            }
+            return;
+        }
+        let finished = input.borrow_mut().finish();
+        match finished {
+            Ok(recording) => {
+                if save(&recorded_name.borrow(), &recording).is_some() {
+                    events::emit(Event::Recorded);
+                }
+            }
+            Err(e) => println!("{}", e),
+        }
+    });
+
+    let shared_state_1 = shared_state.clone();
+    let entries_1 = entries.clone();
+    button_compare.connect_clicked(move |_| {
+        let state = shared_state_1.lock().unwrap();
+        let name = current_entry(&state, &entries_1);
+        match recordings(&name).pop() {
+            Some(mine) => state.audio.compare(&state.manifest.main_clip(&name), mine),
+            None => println!("No recording of {} yet", name),
+        }
+    });
+
+    let shared_state_2 = shared_state.clone();
+    let combo_recordings_2 = combo_recordings.clone();
+    button_play_mine.connect_clicked(move |_| {
+        if let Some(path) = combo_recordings_2.active_id() {
+            shared_state_2
+                .lock()
+                .unwrap()
+                .audio
+                .play_file(PathBuf::from(path.as_str()));
+        }
+    });
+
+    events::subscribe(move |event| {
+        if matches!(event, Event::CardShown | Event::Recorded) {
+            let name = current_entry(&shared_state.lock().unwrap(), &entries);
+            fill_recordings(&combo_recordings, &name);
+        }
+    });
+
+    h_box
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_input_round_trip() {
+        let dir = env::temp_dir().join(format!("thai-record-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // A second of a 220 Hz tone stands in for the learner.
+        let tone = Recording {
+            samples: (0..8000)
+                .map(|n| (n as f32 * 220.0 * std::f32::consts::TAU / 8000.0).sin() * 0.5)
+                .collect(),
+            channels: 1,
+            sample_rate: 8000,
+        };
+        let source = dir.join("voice.wav");
+        write_wav(&source, &tone).unwrap();
+
+        let mut input = FileInput { path: source };
+        input.start().unwrap();
+        let recording = input.finish().unwrap();
+        assert_eq!(recording.channels, 1);
+        assert_eq!(recording.sample_rate, 8000);
+        assert_eq!(recording.samples.len(), tone.samples.len());
+
+        let takes = dir.join("ก");
+        let first = save_in(&takes, &recording).unwrap();
+        let second = save_in(&takes, &recording).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(recordings_in(&takes), vec![first, second]);
+        assert!(recordings_in(&dir.join("ข")).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
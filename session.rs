@@ -0,0 +1,183 @@
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, Button, Label};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+const LISTED: usize = 5;
+
+/// A study session: starts with the first card or answer and lasts until
+/// `end` is called.
+#[derive(Clone, Default)]
+pub struct Session {
+    started: Option<Instant>,
+    current: Option<(String, Instant)>,
+    views: usize,
+    seen: HashSet<String>,
+    recall_times: HashMap<String, Vec<Duration>>,
+    correct: usize,
+    answered: usize,
+    confusions: HashMap<(String, String), usize>,
+}
+
+pub struct Summary {
+    pub duration: Duration,
+    pub views: usize,
+    pub unique: usize,
+    pub correct: usize,
+    pub answered: usize,
+    /// Letters with the longest average time from being shown to being revealed.
+    pub slowest: Vec<(String, Duration)>,
+    /// (expected, given) pairs, most frequent first.
+    pub confused: Vec<(String, String, usize)>,
+}
+
+impl Session {
+    fn touch(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    /// A card was put on screen.
+    pub fn show_card(&mut self, letter: &str) {
+        self.touch();
+        self.views += 1;
+        self.seen.insert(letter.to_string());
+        self.current = Some((letter.to_string(), Instant::now()));
+    }
+
+    /// The answer of the current card was revealed; the time it stayed hidden
+    /// counts as the time needed to recall it.
+    pub fn reveal(&mut self) {
+        if let Some((letter, shown)) = self.current.take() {
+            self.recall_times
+                .entry(letter)
+                .or_default()
+                .push(shown.elapsed());
+        }
+    }
+
+    /// Records an answer about `expected`. `given` is the letter answered
+    /// instead, when the exercise knows it.
+    pub fn answer(&mut self, expected: &str, given: Option<&str>, correct: bool) {
+        self.touch();
+        self.answered += 1;
+        if correct {
+            self.correct += 1;
+            return;
+        }
+        if let Some(given) = given.filter(|g| !g.is_empty() && *g != expected) {
+            *self
+                .confusions
+                .entry((expected.to_string(), given.to_string()))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Ends the session, returning its summary if anything was studied.
+    pub fn end(&mut self) -> Option<Summary> {
+        let started = self.started?;
+
+        let mut slowest: Vec<(String, Duration)> = self
+            .recall_times
+            .iter()
+            .map(|(l, times)| {
+                (
+                    l.clone(),
+                    times.iter().sum::<Duration>() / times.len() as u32,
+                )
+            })
+            .collect();
+        slowest.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+        slowest.truncate(LISTED);
+
+        let mut confused: Vec<(String, String, usize)> = self
+            .confusions
+            .iter()
+            .map(|((e, g), n)| (e.clone(), g.clone(), *n))
+            .collect();
+        confused.sort_by_key(|(_, _, n)| std::cmp::Reverse(*n));
+        confused.truncate(LISTED);
+
+        let summary = Summary {
+            duration: started.elapsed(),
+            views: self.views,
+            unique: self.seen.len(),
+            correct: self.correct,
+            answered: self.answered,
+            slowest,
+            confused,
+        };
+        *self = Session::default();
+        Some(summary)
+    }
+}
+
+impl Summary {
+    fn text(&self) -> String {
+        let secs = self.duration.as_secs();
+        let mut txt = format!(
+            "Time spent: {}:{:02}\nCards seen: {} ({} different)\n",
+            secs / 60,
+            secs % 60,
+            self.views,
+            self.unique
+        );
+
+        txt.push_str(&match self.answered {
+            0 => String::from("Accuracy: no answers given\n"),
+            n => format!(
+                "Accuracy: {}% ({} of {})\n",
+                self.correct * 100 / n,
+                self.correct,
+                n
+            ),
+        });
+
+        txt.push_str("\nSlowest letters:\n");
+        if self.slowest.is_empty() {
+            txt.push_str("  none revealed\n");
+        }
+        for (letter, time) in &self.slowest {
+            txt.push_str(&format!("  {}  {:.1}s\n", letter, time.as_secs_f64()));
+        }
+
+        txt.push_str("\nMost confused:\n");
+        if self.confused.is_empty() {
+            txt.push_str("  none\n");
+        }
+        for (expected, given, n) in &self.confused {
+            txt.push_str(&format!("  {} answered as {}  ×{}\n", expected, given, n));
+        }
+
+        txt
+    }
+}
+
+pub fn build_summary_window(app: &Application, summary: &Summary) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Session summary")
+        .default_width(350)
+        .default_height(400)
+        .build();
+
+    let label_summary = Label::new(None);
+    label_summary.set_markup(&format!(
+        "<span font_desc='Noto Looped Thai UI Normal 12'>{}</span>",
+        pango::glib::markup_escape_text(&summary.text())
+    ));
+    let button_close = Button::with_label("Close");
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    v_box.pack_start(&label_summary, false, false, 0);
+    v_box.pack_start(&button_close, false, false, 0);
+
+    let window_1 = window.clone();
+    button_close.connect_clicked(move |_| window_1.close());
+
+    window.add(&v_box);
+    window.show_all();
+
+    window
+}
@@ -0,0 +1,69 @@
+use crate::storage;
+use std::path::PathBuf;
+
+const SETTINGS_FILE: &str = "settings.tsv";
+
+/// Preferences kept between runs, one `name<TAB>value` record each.
+#[derive(Clone)]
+pub struct Settings {
+    /// Play the recording as soon as Next, Previous or Random shows a card.
+    pub play_on_navigation: bool,
+    /// Play the recording when the answer is revealed with Show.
+    pub play_on_reveal: bool,
+    /// Playback speed of recordings, from 0.5 to 1.5.
+    pub speed: f32,
+    /// Recordings here are played instead of the built-in ones.
+    pub audio_folder: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            play_on_navigation: false,
+            play_on_reveal: true,
+            speed: 1.0,
+            audio_folder: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        let mut settings = Settings::default();
+        for record in storage::read_records(&storage::config_file(SETTINGS_FILE)) {
+            match record.as_slice() {
+                [name, value] if name == "play_on_navigation" => {
+                    settings.play_on_navigation = value == "1"
+                }
+                [name, value] if name == "play_on_reveal" => settings.play_on_reveal = value == "1",
+                [name, value] if name == "speed" => {
+                    settings.speed = value.parse().unwrap_or(1.0f32).clamp(0.5, 1.5)
+                }
+                [name, value] if name == "audio_folder" => {
+                    settings.audio_folder = Some(PathBuf::from(value))
+                }
+                _ => (),
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        let flag = |on: bool| String::from(if on { "1" } else { "0" });
+        let mut records = vec![
+            vec![
+                String::from("play_on_navigation"),
+                flag(self.play_on_navigation),
+            ],
+            vec![String::from("play_on_reveal"), flag(self.play_on_reveal)],
+            vec![String::from("speed"), self.speed.to_string()],
+        ];
+        if let Some(folder) = &self.audio_folder {
+            records.push(vec![
+                String::from("audio_folder"),
+                folder.display().to_string(),
+            ]);
+        }
+        storage::write_records(&storage::config_file(SETTINGS_FILE), &records);
+    }
+}
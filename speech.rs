@@ -0,0 +1,111 @@
+use crate::paths;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+
+// The clips of `audio/`, compiled in by build.rs as (file name, data), and
+// the text of `audio/manifest.tsv`.
+include!(concat!(env!("OUT_DIR"), "/clips.rs"));
+
+/// Extensions of the audio formats that can be played.
+const FORMATS: [&str; 4] = ["mp3", "ogg", "wav", "flac"];
+
+/// The text of a name without its audio extension, e.g. "ก ไก่" for
+/// "ก ไก่.mp3".
+pub fn spoken_text(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((text, extension)) if FORMATS.contains(&extension) => text,
+        _ => name,
+    }
+}
+
+/// Files a clip may be kept in: the name itself if it is a file name,
+/// otherwise the name in each format.
+fn file_names(name: &str) -> Vec<String> {
+    if spoken_text(name) != name {
+        return vec![name.to_string()];
+    }
+    FORMATS.iter().map(|f| format!("{}.{}", name, f)).collect()
+}
+
+/// Something that can turn a clip file name or a syllable, e.g. "ก ไก่.mp3"
+/// or "กา", into audio data rodio can decode.
+pub trait Speech: Send {
+    /// The encoded audio, or None if this backend can't say `text`.
+    fn speak(&self, text: &str) -> Option<Vec<u8>>;
+}
+
+/// Recorded clips. A file in `folder` or the installed assets takes the
+/// place of the built-in clip of the same name, so custom decks can bring
+/// their own.
+pub struct Recordings {
+    pub folder: Option<PathBuf>,
+}
+
+impl Recordings {
+    /// The file of a clip outside the binary: in `folder` if given, otherwise
+    /// in `audio/` of the asset folders.
+    fn clip_file(&self, file: &str) -> Option<PathBuf> {
+        self.folder
+            .as_deref()
+            .map(|f| f.join(file))
+            .filter(|path| path.is_file())
+            .or_else(|| paths::find_asset(&format!("audio/{}", file)))
+    }
+}
+
+impl Speech for Recordings {
+    fn speak(&self, text: &str) -> Option<Vec<u8>> {
+        let files = file_names(text);
+        if let Some(data) = files
+            .iter()
+            .find_map(|file| self.clip_file(file))
+            .and_then(|path| fs::read(path).ok())
+        {
+            return Some(data);
+        }
+        CLIPS
+            .iter()
+            .find(|(clip, _)| files.iter().any(|file| file == clip))
+            .map(|(_, data)| data.to_vec())
+    }
+}
+
+/// The Thai voice of espeak-ng, run as a subprocess for text without a
+/// recording. Does nothing when espeak-ng isn't installed. It runs to the
+/// end before returning, so on the audio thread nothing else plays until
+/// it is done; for the short entries of a deck that is a fraction of a
+/// second.
+pub struct Espeak;
+
+impl Speech for Espeak {
+    fn speak(&self, text: &str) -> Option<Vec<u8>> {
+        let output = match Command::new("espeak-ng")
+            // Entries starting with a dash are text, not options.
+            .args(["-v", "th", "--stdout", "--", spoken_text(text)])
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => return None,
+            Err(e) => {
+                println!("Can't run espeak-ng: {}", e);
+                return None;
+            }
+        };
+        if !output.status.success() || output.stdout.is_empty() {
+            println!(
+                "espeak-ng failed on {}: {}",
+                text,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        Some(output.stdout)
+    }
+}
+
+/// The backends to try in order: recordings first, then speech synthesis.
+pub fn backends(folder: Option<PathBuf>) -> Vec<Box<dyn Speech>> {
+    vec![Box::new(Recordings { folder }), Box::new(Espeak)]
+}
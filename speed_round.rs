@@ -0,0 +1,374 @@
+use crate::deck::{all_decks, Deck};
+use crate::storage;
+use crate::thai::normalize;
+use crate::{Context, Letter};
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, Button, ComboBoxText, Entry, Label};
+use pango::glib::{markup_escape_text, random_int_range, ControlFlow, SourceId};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const ROUND: Duration = Duration::from_secs(60);
+const HIGH_SCORES_FILE: &str = "high_scores.tsv";
+const HIGH_SCORES_KEPT: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    /// The Thai letter is shown, its romanization is typed.
+    Romanization,
+    /// The letter name is shown, the Thai letter is typed.
+    Thai,
+}
+
+impl Mode {
+    fn from_id(id: &str) -> Mode {
+        match id {
+            "thai" => Mode::Thai,
+            _ => Mode::Romanization,
+        }
+    }
+
+    fn id(&self) -> &'static str {
+        match self {
+            Mode::Romanization => "romanization",
+            Mode::Thai => "thai",
+        }
+    }
+}
+
+struct SpeedRound {
+    deck: Deck,
+    mode: Mode,
+    current: usize,
+    correct: usize,
+    answered: usize,
+    started: Option<Instant>,
+}
+
+impl SpeedRound {
+    fn new(deck: Deck, mode: Mode) -> SpeedRound {
+        SpeedRound {
+            deck,
+            mode,
+            current: 0,
+            correct: 0,
+            answered: 0,
+            started: None,
+        }
+    }
+
+    fn next(&mut self) {
+        let len = self.deck.indices.len() as i32;
+        if len == 0 {
+            return;
+        }
+        let mut r = random_int_range(0, len) as usize;
+        if len > 1 && self.deck.indices[r] == self.current {
+            r = (r + 1) % len as usize;
+        }
+        self.current = self.deck.indices[r];
+    }
+
+    fn check(&self, letters: &[Letter], answer: &str) -> bool {
+        let l = &letters[self.current];
+        match self.mode {
+            Mode::Romanization => normalize(answer) == normalize(&l.english_letter),
+            // Several vowels share a name, so any entry with the same name counts.
+            Mode::Thai => self.deck.indices.iter().map(|i| &letters[*i]).any(|o| {
+                o.pronunciation == l.pronunciation
+                    && (answer.trim() == o.letter || answer.trim() == o.glyphs())
+            }),
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        match self.started {
+            Some(started) => ROUND.saturating_sub(started.elapsed()),
+            None => ROUND,
+        }
+    }
+
+    fn accuracy(&self) -> usize {
+        (self.correct * 100).checked_div(self.answered).unwrap_or(0)
+    }
+
+    fn letters_per_minute(&self) -> usize {
+        let elapsed = self
+            .started
+            .map_or(ROUND, |s| s.elapsed().min(ROUND))
+            .as_secs_f64();
+        if elapsed < 1.0 {
+            0
+        } else {
+            (self.correct as f64 * 60.0 / elapsed).round() as usize
+        }
+    }
+
+    fn stats(&self) -> String {
+        format!(
+            "Time: {}s   Letters/min: {}   Accuracy: {}%",
+            self.remaining().as_secs(),
+            self.letters_per_minute(),
+            self.accuracy()
+        )
+    }
+}
+
+/// Stores the score and keeps only the best ones per deck and mode.
+fn save_high_score(round: &SpeedRound) {
+    let mut records = storage::read_records(&storage::data_file(HIGH_SCORES_FILE));
+    records.push(vec![
+        round.deck.name.clone(),
+        round.mode.id().to_string(),
+        round.letters_per_minute().to_string(),
+        round.accuracy().to_string(),
+        storage::today(),
+    ]);
+    sort_scores(&mut records);
+
+    let mut kept: Vec<Vec<String>> = Vec::new();
+    for record in records {
+        let same = kept
+            .iter()
+            .filter(|r| r[0] == record[0] && r[1] == record[1])
+            .count();
+        if same < HIGH_SCORES_KEPT {
+            kept.push(record);
+        }
+    }
+    storage::write_records(&storage::data_file(HIGH_SCORES_FILE), &kept);
+}
+
+fn sort_scores(records: &mut [Vec<String>]) {
+    let score = |r: &Vec<String>| -> (usize, usize) {
+        (
+            r.get(2).and_then(|s| s.parse().ok()).unwrap_or(0),
+            r.get(3).and_then(|s| s.parse().ok()).unwrap_or(0),
+        )
+    };
+    records.sort_by_key(|r| std::cmp::Reverse(score(r)));
+}
+
+fn high_scores_text(deck: &str, mode: Mode) -> String {
+    let mut records: Vec<Vec<String>> =
+        storage::read_records(&storage::data_file(HIGH_SCORES_FILE))
+            .into_iter()
+            .filter(|r| r.len() >= 5 && r[0] == deck && r[1] == mode.id())
+            .collect();
+    sort_scores(&mut records);
+
+    let mut txt = format!("High scores: {}", deck);
+    if records.is_empty() {
+        txt.push_str("\nNone yet");
+    }
+    for (n, r) in records.iter().enumerate() {
+        txt.push_str(&format!(
+            "\n{}. {} letters/min, {}% accuracy ({})",
+            n + 1,
+            r[2],
+            r[3],
+            r[4]
+        ));
+    }
+    txt
+}
+
+fn show_prompt(round: &SpeedRound, letters: &[Letter], label_prompt: &Label) {
+    let l = &letters[round.current];
+    let txt = match round.mode {
+        Mode::Romanization => format!(
+            "<span font_desc='Noto Looped Thai UI Normal 60'>{}</span>",
+            markup_escape_text(&l.letter)
+        ),
+        Mode::Thai => format!(
+            "<span font_desc='Normal 40'>{}</span>",
+            markup_escape_text(&l.pronunciation)
+        ),
+    };
+    label_prompt.set_markup(&txt);
+}
+
+pub fn build_speed_round_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Speed round")
+        .default_width(600)
+        .default_height(450)
+        .build();
+
+    let decks = all_decks(&letters.lock().unwrap());
+
+    let combo_deck = ComboBoxText::new();
+    for deck in &decks {
+        combo_deck.append_text(&deck.name);
+    }
+    combo_deck.set_active(Some(0));
+
+    let combo_mode = ComboBoxText::new();
+    combo_mode.append(Some("romanization"), "Type the romanization");
+    combo_mode.append(Some("thai"), "Type the Thai letter");
+    combo_mode.set_active_id(Some("romanization"));
+
+    let button_start = Button::with_label("Start");
+    let label_prompt = Label::new(Some(""));
+    let label_stats = Label::new(Some(""));
+    let label_scores = Label::new(Some(&high_scores_text(&decks[0].name, Mode::Romanization)));
+    let entry = Entry::new();
+    entry.set_sensitive(false);
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_options = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box_options.pack_start(&combo_deck, true, true, 0);
+    h_box_options.pack_start(&combo_mode, true, true, 0);
+    h_box_options.pack_start(&button_start, false, false, 0);
+
+    v_box.pack_start(&h_box_options, false, false, 0);
+    v_box.pack_start(&label_prompt, false, false, 0);
+    v_box.pack_start(&entry, false, false, 0);
+    v_box.pack_start(&label_stats, false, false, 0);
+    v_box.pack_start(&label_scores, false, false, 0);
+
+    let round = Arc::new(Mutex::new(SpeedRound::new(
+        decks[0].clone(),
+        Mode::Romanization,
+    )));
+
+    let update_scores = {
+        let combo_deck = combo_deck.clone();
+        let combo_mode = combo_mode.clone();
+        let label_scores = label_scores.clone();
+        move || {
+            let deck = combo_deck.active_text().unwrap();
+            let mode = Mode::from_id(&combo_mode.active_id().unwrap());
+            label_scores.set_text(&high_scores_text(&deck, mode));
+        }
+    };
+    let update_scores_1 = update_scores.clone();
+    combo_deck.connect_changed(move |_| update_scores_1());
+    let update_scores_2 = update_scores.clone();
+    combo_mode.connect_changed(move |_| update_scores_2());
+
+    let round_1 = round.clone();
+    let letters_1 = letters.clone();
+    let entry_1 = entry.clone();
+    let label_prompt_1 = label_prompt.clone();
+    let label_stats_1 = label_stats.clone();
+    let combo_deck_1 = combo_deck.clone();
+    let combo_mode_1 = combo_mode.clone();
+    let shared_state_1 = shared_state.clone();
+    // The clock of the running round, stopped if the window is closed first.
+    let timer: Rc<RefCell<Option<SourceId>>> = Rc::new(RefCell::new(None));
+    let timer_1 = timer.clone();
+    button_start.connect_clicked(move |button| {
+        let mut deck = decks[combo_deck_1.active().unwrap() as usize].clone();
+        deck.indices = shared_state_1
+            .lock()
+            .unwrap()
+            .unlock
+            .unlocked_of(&letters_1.lock().unwrap(), &deck.indices);
+        if deck.indices.is_empty() {
+            label_prompt_1.set_text("This deck has no letters to ask");
+            return;
+        }
+        let mode = Mode::from_id(&combo_mode_1.active_id().unwrap());
+        let mut r = SpeedRound::new(deck, mode);
+        r.next();
+        r.started = Some(Instant::now());
+        show_prompt(&r, &letters_1.lock().unwrap(), &label_prompt_1);
+        label_stats_1.set_text(&r.stats());
+        *round_1.lock().unwrap() = r;
+
+        button.set_sensitive(false);
+        combo_deck_1.set_sensitive(false);
+        combo_mode_1.set_sensitive(false);
+        entry_1.set_text("");
+        entry_1.set_sensitive(true);
+        entry_1.grab_focus();
+
+        let round_2 = round_1.clone();
+        let button_1 = button.clone();
+        let combo_deck_2 = combo_deck_1.clone();
+        let combo_mode_2 = combo_mode_1.clone();
+        let entry_2 = entry_1.clone();
+        let label_prompt_2 = label_prompt_1.clone();
+        let label_stats_2 = label_stats_1.clone();
+        let update_scores_3 = update_scores.clone();
+        let timer_2 = timer_1.clone();
+        let source = pango::glib::timeout_add_local(Duration::from_millis(200), move || {
+            let r = round_2.lock().unwrap();
+            label_stats_2.set_text(&r.stats());
+            if !r.remaining().is_zero() {
+                return ControlFlow::Continue;
+            }
+            timer_2.borrow_mut().take();
+
+            save_high_score(&r);
+            label_prompt_2.set_text(&format!(
+                "Time is up: {} correct out of {}",
+                r.correct, r.answered
+            ));
+            drop(r);
+
+            entry_2.set_sensitive(false);
+            button_1.set_sensitive(true);
+            combo_deck_2.set_sensitive(true);
+            combo_mode_2.set_sensitive(true);
+            update_scores_3();
+            ControlFlow::Break
+        });
+        *timer_1.borrow_mut() = Some(source);
+    });
+
+    // An abandoned round gets no high score.
+    window.connect_destroy(move |_| {
+        if let Some(source) = timer.borrow_mut().take() {
+            source.remove();
+        }
+    });
+
+    entry.connect_activate(move |entry| {
+        let mut r = round.lock().unwrap();
+        if r.remaining().is_zero() {
+            return;
+        }
+        let letters = letters.lock().unwrap();
+        let answer = entry.text().trim().to_string();
+        let correct = r.check(&letters, &answer);
+        if correct {
+            r.correct += 1;
+        }
+
+        // A Thai answer naming another deck letter is a confusion between the two.
+        let given = match r.mode {
+            Mode::Thai => r
+                .deck
+                .indices
+                .iter()
+                .map(|i| &letters[*i])
+                .find(|o| o.letter == answer || o.glyphs() == answer)
+                .map(|o| o.letter.as_str()),
+            Mode::Romanization => None,
+        };
+        let mut state = shared_state.lock().unwrap();
+        state.answer(&letters[r.current].letter, given, correct);
+        state.goals.study();
+        drop(state);
+
+        r.answered += 1;
+        r.next();
+        show_prompt(&r, &letters, &label_prompt);
+        label_stats.set_text(&r.stats());
+        entry.set_text("");
+    });
+
+    window.add(&v_box);
+    window.show_all();
+
+    window
+}
@@ -0,0 +1,51 @@
+use crate::paths;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn file_in(dir: PathBuf, name: &str) -> PathBuf {
+    if let Err(e) = fs::create_dir_all(&dir) {
+        println!("Can't create {}: {}", dir.display(), e)
+    }
+    dir.join(name)
+}
+
+/// Path of a file holding user data such as scores and progress.
+pub fn data_file(name: &str) -> PathBuf {
+    file_in(paths::data_dir(), name)
+}
+
+/// Path of a file holding preferences.
+pub fn config_file(name: &str) -> PathBuf {
+    file_in(paths::config_dir(), name)
+}
+
+/// Reads a tab separated file, one record per line. A missing file is empty.
+pub fn read_records(path: &Path) -> Vec<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.split('\t').map(String::from).collect())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn write_records(path: &Path, records: &[Vec<String>]) {
+    let content: String = records
+        .iter()
+        .map(|record| record.join("\t") + "\n")
+        .collect();
+
+    if let Err(e) = fs::write(path, content) {
+        println!("Can't write {}: {}", path.display(), e)
+    }
+}
+
+/// Today's local date as YYYY-MM-DD.
+pub fn today() -> String {
+    pango::glib::DateTime::now_local()
+        .and_then(|now| now.format("%Y-%m-%d"))
+        .map(|d| d.to_string())
+        .unwrap_or_default()
+}
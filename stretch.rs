@@ -0,0 +1,157 @@
+use std::f32::consts::PI;
+
+/// Length of a window in seconds; long enough to hold a few pitch periods.
+const WINDOW_SECS: f32 = 0.04;
+/// Only every this many samples are compared when lining windows up.
+const SEARCH_STEP: usize = 4;
+
+/// Changes the speed of interleaved samples without changing their pitch, so
+/// tones still sound right when slowed down; `speed` below 1 makes the clip
+/// longer. Uses WSOLA: overlapping windows are taken from the input at the
+/// new rate, each shifted a little to line up with the previous one.
+pub fn time_stretch(samples: &[f32], channels: usize, sample_rate: u32, speed: f32) -> Vec<f32> {
+    let channels = channels.max(1);
+    let frames = samples.len() / channels;
+    let window = (sample_rate as f32 * WINDOW_SECS) as usize;
+    if frames < window * 2 || (speed - 1.0).abs() < 0.01 {
+        return samples.to_vec();
+    }
+
+    // Windows are lined up on the mix of all channels.
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    let hop_out = window / 2;
+    let hop_in = hop_out as f32 * speed;
+    let tolerance = window / 4;
+    let hann: Vec<f32> = (0..window)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / window as f32).cos())
+        .collect();
+
+    let out_frames = (frames as f32 / speed) as usize + window;
+    let mut out = vec![0.0; out_frames * channels];
+    let mut previous = 0;
+    let mut k = 0;
+    loop {
+        let nominal = (k as f32 * hop_in) as usize;
+        if nominal + window + tolerance >= frames || k * hop_out + window >= out_frames {
+            break;
+        }
+        let start = if k == 0 {
+            0
+        } else {
+            best_offset(&mono, previous + hop_out, nominal, tolerance, window)
+        };
+
+        let out_start = k * hop_out;
+        for n in 0..window {
+            for c in 0..channels {
+                out[(out_start + n) * channels + c] +=
+                    hann[n] * samples[(start + n) * channels + c];
+            }
+        }
+        previous = start;
+        k += 1;
+    }
+
+    out.truncate((k * hop_out + hop_out) * channels);
+    out
+}
+
+/// The start near `nominal` whose window best continues the audio at
+/// `natural`, where the previous window would have gone on.
+fn best_offset(
+    mono: &[f32],
+    natural: usize,
+    nominal: usize,
+    tolerance: usize,
+    window: usize,
+) -> usize {
+    let natural = natural.min(mono.len() - window);
+    let from = nominal.saturating_sub(tolerance);
+    let to = (nominal + tolerance).min(mono.len() - window);
+
+    let mut best = nominal.min(to);
+    let mut best_score = f32::MIN;
+    for start in from..=to {
+        let score: f32 = (0..window)
+            .step_by(SEARCH_STEP)
+            .map(|n| mono[start + n] * mono[natural + n])
+            .sum();
+        if score > best_score {
+            best_score = score;
+            best = start;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8000;
+
+    /// A second of a 220 Hz tone, the same on each channel.
+    fn tone(channels: usize) -> Vec<f32> {
+        (0..RATE as usize)
+            .flat_map(|n| {
+                let x = (2.0 * PI * 220.0 * n as f32 / RATE as f32).sin();
+                std::iter::repeat_n(x, channels)
+            })
+            .collect()
+    }
+
+    /// Sign changes per frame over the middle half of a mono signal.
+    fn crossing_rate(mono: &[f32]) -> f32 {
+        let middle = &mono[mono.len() / 4..mono.len() * 3 / 4];
+        let crossings = middle
+            .windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count();
+        crossings as f32 / middle.len() as f32
+    }
+
+    #[test]
+    fn length_follows_speed() {
+        let window = (RATE as f32 * WINDOW_SECS) as usize;
+        for speed in [0.5, 0.75, 1.5, 2.0] {
+            let out = time_stretch(&tone(2), 2, RATE, speed);
+            assert_eq!(out.len() % 2, 0);
+            let expected = RATE as f32 / speed;
+            let frames = (out.len() / 2) as f32;
+            assert!(
+                (frames - expected).abs() <= 3.0 * window as f32,
+                "speed {}: {} frames, expected about {}",
+                speed,
+                frames,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn pitch_is_kept() {
+        let input = tone(1);
+        for speed in [0.5, 2.0] {
+            let out = time_stretch(&input, 1, RATE, speed);
+            let ratio = crossing_rate(&out) / crossing_rate(&input);
+            assert!(
+                (ratio - 1.0).abs() < 0.05,
+                "speed {}: ratio {}",
+                speed,
+                ratio
+            );
+        }
+    }
+
+    #[test]
+    fn normal_speed_and_short_clips_are_untouched() {
+        let input = tone(1);
+        assert_eq!(time_stretch(&input, 1, RATE, 1.0), input);
+        let short = &input[..100];
+        assert_eq!(time_stretch(short, 1, RATE, 0.5), short);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::deck;
+use crate::storage;
+use crate::Letter;
+use gtk::prelude::*;
+use gtk::{
+    Application, ApplicationWindow, Button, CheckButton, ComboBoxText, Entry, FileChooserAction,
+    FileChooserDialog, Label, ResponseType,
+};
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+const STUDY_SETS_FILE: &str = "study_sets.tsv";
+const COLUMNS: i32 = 8;
+
+/// A named, hand-picked list of letters, e.g. "letters I mix up".
+#[derive(Clone)]
+pub struct StudySet {
+    pub name: String,
+    pub letters: Vec<String>,
+}
+
+/// Each set is stored on one line: its name followed by its letters.
+pub fn load_sets() -> Vec<StudySet> {
+    storage::read_records(&storage::data_file(STUDY_SETS_FILE))
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .map(|r| StudySet {
+            name: r[0].clone(),
+            letters: r[1..].to_vec(),
+        })
+        .collect()
+}
+
+fn save_sets(sets: &[StudySet]) {
+    let records: Vec<Vec<String>> = sets
+        .iter()
+        .map(|s| {
+            let mut record = vec![s.name.clone()];
+            record.extend(s.letters.iter().cloned());
+            record
+        })
+        .collect();
+    storage::write_records(&storage::data_file(STUDY_SETS_FILE), &records);
+}
+
+/// Writes the set as a small text file: the name, then one letter per line
+/// with its romanization, so it can be shared or printed.
+fn export_set(set: &StudySet, letters: &[Letter], path: &Path) {
+    let mut content = format!("# {}\n", set.name);
+    for l in letters.iter().filter(|l| set.letters.contains(&l.letter)) {
+        content.push_str(&format!("{}\t{}\n", l.letter, l.english_letter));
+    }
+    if let Err(e) = fs::write(path, content) {
+        println!("Can't export study set: {}", e)
+    }
+}
+
+fn fill_sets_combo(combo: &ComboBoxText, sets: &[StudySet]) {
+    combo.remove_all();
+    for set in sets {
+        combo.append(Some(&set.name), &set.name);
+    }
+}
+
+/// Opens the set builder. `on_change` is called after a set was saved or
+/// deleted so lists of decks elsewhere can be refreshed.
+pub fn build_study_sets_window<F: Fn() + 'static>(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    on_change: F,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Study sets")
+        .default_width(600)
+        .default_height(500)
+        .build();
+
+    let on_change = Rc::new(on_change);
+
+    let combo_sets = ComboBoxText::new();
+    fill_sets_combo(&combo_sets, &load_sets());
+    let entry_name = Entry::new();
+    entry_name.set_placeholder_text(Some("Set name, e.g. lesson 4"));
+    let button_save = Button::with_label("Save");
+    let button_delete = Button::with_label("Delete");
+    let button_export = Button::with_label("Export…");
+    let label_status = Label::new(None);
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(4);
+    grid.set_column_spacing(12);
+    let mut checks = Vec::new();
+    for (n, l) in letters.lock().unwrap().iter().enumerate() {
+        let check = CheckButton::new();
+        let label = Label::new(None);
+        label.set_markup(&format!(
+            "<span font_desc='Noto Looped Thai UI Normal 16'>{}</span>",
+            l.letter
+        ));
+        check.add(&label);
+        grid.attach(&check, n as i32 % COLUMNS, n as i32 / COLUMNS, 1, 1);
+        checks.push((l.letter.clone(), check));
+    }
+    let checks = Rc::new(checks);
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_name = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    let h_box_buttons = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box_name.pack_start(&combo_sets, true, true, 0);
+    h_box_name.pack_start(&entry_name, true, true, 0);
+    h_box_buttons.pack_start(&button_save, true, false, 0);
+    h_box_buttons.pack_start(&button_delete, true, false, 0);
+    h_box_buttons.pack_start(&button_export, true, false, 0);
+
+    v_box.pack_start(&h_box_name, false, false, 0);
+    v_box.pack_start(&grid, false, false, 0);
+    v_box.pack_start(&h_box_buttons, false, false, 0);
+    v_box.pack_start(&label_status, false, false, 0);
+
+    let checks_1 = checks.clone();
+    let entry_name_1 = entry_name.clone();
+    combo_sets.connect_changed(move |combo| {
+        let name = match combo.active_id() {
+            Some(name) => name,
+            None => return,
+        };
+        if let Some(set) = load_sets().into_iter().find(|s| s.name == name) {
+            entry_name_1.set_text(&set.name);
+            for (letter, check) in checks_1.iter() {
+                check.set_active(set.letters.contains(letter));
+            }
+        }
+    });
+
+    let selected = {
+        let checks = checks.clone();
+        move || -> Vec<String> {
+            checks
+                .iter()
+                .filter(|(_, check)| check.is_active())
+                .map(|(letter, _)| letter.clone())
+                .collect()
+        }
+    };
+
+    let combo_sets_2 = combo_sets.clone();
+    let entry_name_2 = entry_name.clone();
+    let label_status_2 = label_status.clone();
+    let on_change_2 = on_change.clone();
+    let selected_2 = selected.clone();
+    button_save.connect_clicked(move |_| {
+        // Tabs separate fields in the sets file.
+        let name = entry_name_2.text().replace('\t', " ").trim().to_string();
+        let chosen = selected_2();
+        if name.is_empty() || chosen.is_empty() {
+            label_status_2.set_text("Give the set a name and pick at least one letter");
+            return;
+        }
+        if deck::is_builtin(&name) {
+            label_status_2.set_text(&format!(
+                "\"{}\" is a built-in deck, pick another name",
+                name
+            ));
+            return;
+        }
+
+        let mut sets = load_sets();
+        match sets.iter_mut().find(|s| s.name == name) {
+            Some(set) => set.letters = chosen,
+            None => sets.push(StudySet {
+                name: name.clone(),
+                letters: chosen,
+            }),
+        }
+        save_sets(&sets);
+        fill_sets_combo(&combo_sets_2, &sets);
+        combo_sets_2.set_active_id(Some(&name));
+        label_status_2.set_text(&format!("Saved \"{}\"", name));
+        on_change_2();
+    });
+
+    let combo_sets_3 = combo_sets.clone();
+    let entry_name_3 = entry_name.clone();
+    let label_status_3 = label_status.clone();
+    button_delete.connect_clicked(move |_| {
+        let name = entry_name_3.text().to_string();
+        let mut sets = load_sets();
+        let before = sets.len();
+        sets.retain(|s| s.name != name);
+        if sets.len() == before {
+            return;
+        }
+        save_sets(&sets);
+        fill_sets_combo(&combo_sets_3, &sets);
+        entry_name_3.set_text("");
+        label_status_3.set_text(&format!("Deleted \"{}\"", name));
+        on_change();
+    });
+
+    let window_4 = window.clone();
+    button_export.connect_clicked(move |_| {
+        let set = StudySet {
+            name: entry_name.text().to_string(),
+            letters: selected(),
+        };
+        let dialog = FileChooserDialog::with_buttons(
+            Some("Export study set"),
+            Some(&window_4),
+            FileChooserAction::Save,
+            &[
+                ("Cancel", ResponseType::Cancel),
+                ("Export", ResponseType::Accept),
+            ],
+        );
+        dialog.set_do_overwrite_confirmation(true);
+        dialog.set_current_name(&format!("{}.txt", set.name));
+
+        let letters_4 = letters.clone();
+        let label_status_4 = label_status.clone();
+        dialog.connect_response(move |dialog, response| {
+            if response == ResponseType::Accept {
+                if let Some(path) = dialog.filename() {
+                    export_set(&set, &letters_4.lock().unwrap(), &path);
+                    label_status_4.set_text(&format!("Exported to {}", path.display()));
+                }
+            }
+            dialog.close();
+        });
+        dialog.show_all();
+    });
+
+    window.add(&v_box);
+    window.show_all();
+
+    window
+}
@@ -0,0 +1,89 @@
+use crate::thai::{final_sound, random_syllable, vowel_form, Syllable, Tone};
+use crate::Letter;
+
+/// Consonants that can start a syllable and the vowels among the `unlocked`
+/// letters, or among all of them if those lack either.
+pub fn syllable_parts(letters: &[Letter], unlocked: &[usize]) -> (Vec<char>, Vec<String>) {
+    let parts_of = |indices: &mut dyn Iterator<Item = usize>| {
+        let chosen: Vec<&Letter> = indices.map(|i| &letters[i]).collect();
+        // ฃ and ฅ are no longer in use and never start a real syllable.
+        let initials: Vec<char> = chosen
+            .iter()
+            .filter(|l| l.consonant && l.letter != "ฃ" && l.letter != "ฅ")
+            .filter_map(|l| l.letter.chars().next())
+            .collect();
+        let vowels: Vec<String> = chosen
+            .iter()
+            .filter(|l| !l.consonant && vowel_form(&l.letter).is_some())
+            .map(|l| l.letter.clone())
+            .collect();
+        (initials, vowels)
+    };
+    let (initials, vowels) = parts_of(&mut unlocked.iter().copied());
+    if initials.is_empty() || vowels.is_empty() {
+        return parts_of(&mut (0..letters.len()));
+    }
+    (initials, vowels)
+}
+
+pub fn new_syllable(letters: &[Letter], unlocked: &[usize]) -> Syllable {
+    let (initials, vowels) = syllable_parts(letters, unlocked);
+    random_syllable(&mut rand::thread_rng(), &initials, &vowels)
+}
+
+/// Combining accent marking the tone on the first vowel, as in the deck's
+/// pronunciations.
+fn tone_accent(tone: Tone) -> Option<char> {
+    match tone {
+        Tone::Mid => None,
+        Tone::Low => Some('\u{0300}'),
+        Tone::Falling => Some('\u{0302}'),
+        Tone::High => Some('\u{0301}'),
+        Tone::Rising => Some('\u{030C}'),
+    }
+}
+
+/// Spells the syllable with the romanization of its letters in the deck.
+pub fn romanization(syllable: &Syllable, letters: &[Letter]) -> String {
+    let sound_of = |letter: &str| {
+        letters
+            .iter()
+            .find(|l| l.letter == letter)
+            .map(|l| l.english_letter.clone())
+            .unwrap_or_default()
+    };
+
+    // อ only carries the vowel and isn't pronounced.
+    let initial = match syllable.initial {
+        'อ' => String::new(),
+        c => sound_of(&c.to_string()),
+    };
+    let mut vowel_sound = sound_of(syllable.vowel.form);
+    let mut final_consonant = syllable
+        .final_consonant
+        .and_then(final_sound)
+        .unwrap_or_default();
+    // Before a glide, iaa and uaa are said short, and ว after i sounds like u.
+    if matches!(syllable.final_consonant, Some('ย') | Some('ว')) {
+        if let Some(short) = vowel_sound
+            .strip_suffix("aa")
+            .filter(|s| s.ends_with(['i', 'u']))
+        {
+            vowel_sound = format!("{}a", short);
+        }
+        if final_consonant == "o" && vowel_sound.ends_with('i') {
+            final_consonant = "u";
+        }
+    }
+    let mut vowel: Vec<char> = vowel_sound.chars().collect();
+    if let (Some(accent), false) = (tone_accent(syllable.tone()), vowel.is_empty()) {
+        vowel.insert(1, accent);
+    }
+
+    format!(
+        "{}{}{}",
+        initial,
+        vowel.into_iter().collect::<String>(),
+        final_consonant
+    )
+}
@@ -0,0 +1,480 @@
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ConsonantClass {
+    Low,
+    Mid,
+    High,
+}
+
+impl fmt::Display for ConsonantClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ConsonantClass::Low => "low",
+            ConsonantClass::Mid => "mid",
+            ConsonantClass::High => "high",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub const MID_CLASS: &str = "กจฎฏดตบปอ";
+pub const HIGH_CLASS: &str = "ขฃฉฐถผฝศษสห";
+
+/// Mid class consonants: chicken (ก) pecks (จ) child (ด) dead (ต) on (บ) the
+/// mouth (ป) of a jar (อ); ฎ and ฏ are their rare look-alikes.
+pub const MID_CLASS_MNEMONIC: &str = "ไก่จิกเด็กตายบนปากโอ่ง";
+
+pub fn consonant_class(c: char) -> Option<ConsonantClass> {
+    if !('ก'..='ฮ').contains(&c) || c == 'ฤ' || c == 'ฦ' {
+        None
+    } else if MID_CLASS.contains(c) {
+        Some(ConsonantClass::Mid)
+    } else if HIGH_CLASS.contains(c) {
+        Some(ConsonantClass::High)
+    } else {
+        Some(ConsonantClass::Low)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Tone {
+    Mid,
+    Low,
+    Falling,
+    High,
+    Rising,
+}
+
+pub const TONES: [Tone; 5] = [
+    Tone::Mid,
+    Tone::Low,
+    Tone::Falling,
+    Tone::High,
+    Tone::Rising,
+];
+
+impl fmt::Display for Tone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Tone::Mid => "mid",
+            Tone::Low => "low",
+            Tone::Falling => "falling",
+            Tone::High => "high",
+            Tone::Rising => "rising",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub const MAI_EK: char = '\u{0E48}';
+pub const MAI_THO: char = '\u{0E49}';
+pub const MAI_TRI: char = '\u{0E4A}';
+pub const MAI_CHATTAWA: char = '\u{0E4B}';
+
+pub fn tone_mark_name(mark: char) -> &'static str {
+    match mark {
+        MAI_EK => "mai ek",
+        MAI_THO => "mai tho",
+        MAI_TRI => "mai tri",
+        MAI_CHATTAWA => "mai chattawa",
+        _ => "unknown mark",
+    }
+}
+
+/// The tone of a syllable from the class of its initial consonant, whether it
+/// is live, the vowel length and its tone mark.
+pub fn tone(class: ConsonantClass, live: bool, long: bool, mark: Option<char>) -> Tone {
+    match (class, mark) {
+        (ConsonantClass::Mid, None) | (ConsonantClass::Low, None) if live => Tone::Mid,
+        (ConsonantClass::High, None) if live => Tone::Rising,
+        (ConsonantClass::Low, None) if long => Tone::Falling,
+        (ConsonantClass::Low, None) => Tone::High,
+        (_, None) => Tone::Low,
+        (ConsonantClass::Low, Some(MAI_EK)) => Tone::Falling,
+        (ConsonantClass::Low, Some(MAI_THO)) => Tone::High,
+        (_, Some(MAI_EK)) => Tone::Low,
+        (_, Some(MAI_THO)) => Tone::Falling,
+        (_, Some(MAI_TRI)) => Tone::High,
+        (_, Some(_)) => Tone::Rising,
+    }
+}
+
+pub struct VowelForm {
+    /// Written around a placeholder อ, as in the deck.
+    pub form: &'static str,
+    pub long: bool,
+    /// How the vowel is written when a final consonant follows, if it can take one.
+    pub closed: Option<&'static str>,
+    /// Which of the glides ย and ว can close it. Elsewhere they would be read
+    /// as another vowel, as in กัว, or aren't written at all, as in กิย.
+    pub glides: &'static str,
+}
+
+#[rustfmt::skip]
+pub const VOWEL_FORMS: [VowelForm; 28] = [
+    VowelForm { form: "อะ", long: false, closed: Some("อั"), glides: "" },
+    VowelForm { form: "อิ", long: false, closed: Some("อิ"), glides: "ว" },
+    VowelForm { form: "อึ", long: false, closed: Some("อึ"), glides: "" },
+    VowelForm { form: "อุ", long: false, closed: Some("อุ"), glides: "ย" },
+    VowelForm { form: "เอะ", long: false, closed: Some("เอ็"), glides: "" },
+    VowelForm { form: "แอะ", long: false, closed: Some("แอ็"), glides: "" },
+    VowelForm { form: "โอะ", long: false, closed: Some("อ"), glides: "" },
+    VowelForm { form: "เอาะ", long: false, closed: None, glides: "" },
+    VowelForm { form: "เออะ", long: false, closed: None, glides: "" },
+    VowelForm { form: "เอียะ", long: false, closed: None, glides: "" },
+    VowelForm { form: "เอือะ", long: false, closed: None, glides: "" },
+    VowelForm { form: "อัวะ", long: false, closed: None, glides: "" },
+    VowelForm { form: "อำ", long: false, closed: None, glides: "" },
+    VowelForm { form: "ไอ", long: false, closed: None, glides: "" },
+    VowelForm { form: "ใอ", long: false, closed: None, glides: "" },
+    VowelForm { form: "เอา", long: false, closed: None, glides: "" },
+    VowelForm { form: "อา", long: true, closed: Some("อา"), glides: "ยว" },
+    VowelForm { form: "อี", long: true, closed: Some("อี"), glides: "" },
+    VowelForm { form: "อือ", long: true, closed: Some("อื"), glides: "" },
+    VowelForm { form: "อู", long: true, closed: Some("อู"), glides: "ย" },
+    VowelForm { form: "เอ", long: true, closed: Some("เอ"), glides: "ว" },
+    VowelForm { form: "แอ", long: true, closed: Some("แอ"), glides: "ว" },
+    VowelForm { form: "โอ", long: true, closed: Some("โอ"), glides: "ย" },
+    VowelForm { form: "ออ", long: true, closed: Some("ออ"), glides: "ย" },
+    VowelForm { form: "เออ", long: true, closed: Some("เอิ"), glides: "ย" },
+    VowelForm { form: "เอีย", long: true, closed: Some("เอีย"), glides: "ว" },
+    VowelForm { form: "เอือ", long: true, closed: Some("เอือ"), glides: "ย" },
+    VowelForm { form: "อัว", long: true, closed: Some("อว"), glides: "ย" },
+];
+
+pub fn vowel_form(form: &str) -> Option<&'static VowelForm> {
+    VOWEL_FORMS.iter().find(|v| v.form == form)
+}
+
+impl VowelForm {
+    /// Finals that may follow the vowel: any of `FINALS` except the glides
+    /// it can't take.
+    pub fn allowed_finals(&self) -> Vec<char> {
+        if self.closed.is_none() {
+            return Vec::new();
+        }
+        FINALS
+            .iter()
+            .map(|(f, _)| *f)
+            .filter(|f| !matches!(f, 'ย' | 'ว') || self.glides.contains(*f))
+            .collect()
+    }
+
+    /// อำ, ไอ, ใอ and เอา are short but end in m, y or w, so they are live.
+    fn ends_in_sonorant(&self) -> bool {
+        matches!(self.form, "อำ" | "ไอ" | "ใอ" | "เอา")
+    }
+}
+
+/// Consonants usable as finals, with the sound they take at the end of a
+/// syllable. The last three sounds are stops, which make a syllable dead.
+pub const FINALS: [(char, &str); 17] = [
+    ('ง', "ng"),
+    ('น', "n"),
+    ('ณ', "n"),
+    ('ร', "n"),
+    ('ล', "n"),
+    ('ม', "m"),
+    ('ย', "i"),
+    ('ว', "o"),
+    ('ก', "k"),
+    ('ข', "k"),
+    ('ค', "k"),
+    ('ด', "t"),
+    ('ต', "t"),
+    ('ท', "t"),
+    ('ส', "t"),
+    ('บ', "p"),
+    ('ป', "p"),
+];
+
+pub fn final_sound(c: char) -> Option<&'static str> {
+    FINALS.iter().find(|(f, _)| *f == c).map(|(_, s)| *s)
+}
+
+pub fn is_stop(c: char) -> bool {
+    matches!(final_sound(c), Some("k") | Some("t") | Some("p"))
+}
+
+/// Vowel signs written above or below the consonant; a tone mark goes after them.
+fn is_vowel_mark(c: char) -> bool {
+    matches!(c, '\u{0E31}' | '\u{0E34}'..='\u{0E39}')
+}
+
+/// Marks written above or below the preceding consonant: vowel signs, tone
+/// marks, ็, ์ and the like.
+fn is_combining(c: char) -> bool {
+    matches!(c, '\u{0E31}' | '\u{0E34}'..='\u{0E3A}' | '\u{0E47}'..='\u{0E4E}')
+}
+
+/// Splits Thai text into what is seen as one character: a letter together
+/// with the marks stacked on it.
+pub fn graphemes(text: &str) -> Vec<String> {
+    let mut graphemes: Vec<String> = Vec::new();
+    for c in text.chars() {
+        match graphemes.last_mut() {
+            Some(last) if is_combining(c) => last.push(c),
+            _ => graphemes.push(c.to_string()),
+        }
+    }
+    graphemes
+}
+
+#[derive(Clone)]
+pub struct Syllable {
+    pub initial: char,
+    pub vowel: &'static VowelForm,
+    pub final_consonant: Option<char>,
+    pub tone_mark: Option<char>,
+}
+
+impl Syllable {
+    pub fn class(&self) -> ConsonantClass {
+        consonant_class(self.initial).unwrap()
+    }
+
+    pub fn is_live(&self) -> bool {
+        match self.final_consonant {
+            Some(f) => !is_stop(f),
+            None => self.vowel.long || self.vowel.ends_in_sonorant(),
+        }
+    }
+
+    pub fn tone(&self) -> Tone {
+        tone(
+            self.class(),
+            self.is_live(),
+            self.vowel.long,
+            self.tone_mark,
+        )
+    }
+
+    pub fn written(&self) -> String {
+        let template = match self.final_consonant {
+            // เออ closed by ย drops its ิ: เกย, not เกิย.
+            Some('ย') if self.vowel.form == "เออ" => "เอ",
+            Some(_) => self.vowel.closed.unwrap(),
+            None => self.vowel.form,
+        };
+        let mut chars: Vec<char> = template.chars().collect();
+        let pos = chars.iter().position(|c| *c == 'อ').unwrap();
+        chars[pos] = self.initial;
+
+        if let Some(mark) = self.tone_mark {
+            let mut after = pos + 1;
+            while after < chars.len() && is_vowel_mark(chars[after]) {
+                after += 1;
+            }
+            chars.insert(after, mark);
+        }
+        if let Some(f) = self.final_consonant {
+            chars.push(f);
+        }
+
+        chars.into_iter().collect()
+    }
+
+    /// The steps taken to work out the tone, one sentence per step.
+    pub fn reasoning(&self) -> Vec<String> {
+        let mut steps = vec![format!(
+            "Initial consonant {} is {} class",
+            self.initial,
+            self.class()
+        )];
+        steps.push(format!(
+            "Vowel {} is {}",
+            self.vowel.form,
+            if self.vowel.long { "long" } else { "short" }
+        ));
+        steps.push(match self.final_consonant {
+            Some(f) if is_stop(f) => format!(
+                "Final {} sounds like -{}, a stop, so the syllable is dead",
+                f,
+                final_sound(f).unwrap()
+            ),
+            Some(f) => format!(
+                "Final {} sounds like -{}, a sonorant, so the syllable is live",
+                f,
+                final_sound(f).unwrap()
+            ),
+            None if self.vowel.long => String::from("Open syllable with a long vowel is live"),
+            None if self.is_live() => {
+                format!(
+                    "{} ends in a sonorant sound, so it is live",
+                    self.vowel.form
+                )
+            }
+            None => String::from("Open syllable with a short vowel is dead"),
+        });
+        steps.push(match self.tone_mark {
+            Some(mark) => format!("Tone mark {} ({})", mark, tone_mark_name(mark)),
+            None => String::from("No tone mark"),
+        });
+        steps.push(format!(
+            "{} class, {}{}, {} → {} tone",
+            self.class(),
+            if self.is_live() { "live" } else { "dead" },
+            if self.tone_mark.is_none() && !self.is_live() && self.class() == ConsonantClass::Low {
+                if self.vowel.long {
+                    ", long vowel"
+                } else {
+                    ", short vowel"
+                }
+            } else {
+                ""
+            },
+            match self.tone_mark {
+                Some(mark) => tone_mark_name(mark),
+                None => "no mark",
+            },
+            self.tone()
+        ));
+        steps
+    }
+}
+
+/// Builds a random valid syllable from the given initial consonants and vowel
+/// forms. Vowels missing from `VOWEL_FORMS` are ignored.
+pub fn random_syllable<R: rand::Rng>(
+    rng: &mut R,
+    initials: &[char],
+    vowels: &[String],
+) -> Syllable {
+    use rand::seq::SliceRandom;
+
+    let vowels: Vec<&'static VowelForm> = vowels.iter().filter_map(|v| vowel_form(v)).collect();
+    let initial = *initials.choose(rng).unwrap();
+    let vowel = *vowels.choose(rng).unwrap();
+
+    let finals = vowel.allowed_finals();
+    let final_consonant = match finals.choose(rng) {
+        Some(f) if rng.gen_bool(0.6) => Some(*f),
+        _ => None,
+    };
+
+    // ็ takes the place of a tone mark, so closed เอ็ and แอ็ are left unmarked.
+    let can_mark = !(final_consonant.is_some() && vowel.closed.unwrap().contains('็'));
+    let marks: &[char] = match consonant_class(initial) {
+        Some(ConsonantClass::Mid) => &[MAI_EK, MAI_THO, MAI_TRI, MAI_CHATTAWA],
+        _ => &[MAI_EK, MAI_THO],
+    };
+    let tone_mark = if can_mark && rng.gen_bool(0.4) {
+        Some(*marks.choose(rng).unwrap())
+    } else {
+        None
+    };
+
+    Syllable {
+        initial,
+        vowel,
+        final_consonant,
+        tone_mark,
+    }
+}
+
+/// Lets the IPA vowels of a romanization be typed on a plain keyboard.
+pub fn normalize(romanization: &str) -> String {
+    romanization
+        .trim()
+        .to_lowercase()
+        .replace('ʉ', "ue")
+        .replace('ɛ', "ae")
+        .replace('ɔ', "o")
+        .replace('ə', "oe")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syllable(
+        initial: char,
+        vowel: &str,
+        final_consonant: Option<char>,
+        mark: Option<char>,
+    ) -> Syllable {
+        Syllable {
+            initial,
+            vowel: vowel_form(vowel).unwrap(),
+            final_consonant,
+            tone_mark: mark,
+        }
+    }
+
+    #[test]
+    fn unmarked_tones() {
+        // Live syllables.
+        assert_eq!(syllable('ก', "อา", None, None).tone(), Tone::Mid);
+        assert_eq!(syllable('ค', "อา", None, None).tone(), Tone::Mid);
+        assert_eq!(syllable('ข', "อา", None, None).tone(), Tone::Rising);
+        assert_eq!(syllable('ก', "อา", Some('น'), None).tone(), Tone::Mid);
+        assert_eq!(syllable('ข', "ไอ", None, None).tone(), Tone::Rising);
+        // Dead syllables.
+        assert_eq!(syllable('ก', "อะ", None, None).tone(), Tone::Low);
+        assert_eq!(syllable('ข', "อา", Some('ด'), None).tone(), Tone::Low);
+        assert_eq!(syllable('ค', "อะ", None, None).tone(), Tone::High);
+        assert_eq!(syllable('ค', "อา", Some('ก'), None).tone(), Tone::Falling);
+        assert_eq!(syllable('ค', "อะ", Some('ด'), None).tone(), Tone::High);
+    }
+
+    #[test]
+    fn marked_tones() {
+        let marked = |initial, mark| syllable(initial, "อา", None, Some(mark)).tone();
+        assert_eq!(marked('ก', MAI_EK), Tone::Low);
+        assert_eq!(marked('ก', MAI_THO), Tone::Falling);
+        assert_eq!(marked('ก', MAI_TRI), Tone::High);
+        assert_eq!(marked('ก', MAI_CHATTAWA), Tone::Rising);
+        assert_eq!(marked('ข', MAI_EK), Tone::Low);
+        assert_eq!(marked('ข', MAI_THO), Tone::Falling);
+        assert_eq!(marked('ค', MAI_EK), Tone::Falling);
+        assert_eq!(marked('ค', MAI_THO), Tone::High);
+        // A mark overrides a dead syllable.
+        assert_eq!(
+            syllable('ค', "อะ", None, Some(MAI_EK)).tone(),
+            Tone::Falling
+        );
+    }
+
+    #[test]
+    fn written_forms() {
+        assert_eq!(syllable('ก', "อา", None, Some(MAI_EK)).written(), "ก่า");
+        assert_eq!(syllable('ก', "อิ", None, Some(MAI_EK)).written(), "กิ่");
+        assert_eq!(syllable('ก', "เอะ", Some('ด'), None).written(), "เก็ด");
+        assert_eq!(syllable('ก', "อะ", Some('น'), None).written(), "กัน");
+        assert_eq!(syllable('ก', "อัว", Some('น'), None).written(), "กวน");
+        assert_eq!(syllable('ก', "เอีย", None, Some(MAI_THO)).written(), "เกี้ย");
+        assert_eq!(syllable('ก', "เออ", Some('ย'), None).written(), "เกย");
+        assert_eq!(syllable('ก', "เออ", Some('น'), None).written(), "เกิน");
+    }
+
+    #[test]
+    fn glides_only_where_legal() {
+        let finals = |vowel| vowel_form(vowel).unwrap().allowed_finals();
+        assert!(!finals("อะ").contains(&'ย') && !finals("อะ").contains(&'ว'));
+        assert!(finals("อา").contains(&'ย') && finals("อา").contains(&'ว'));
+        assert!(finals("อิ").contains(&'ว') && !finals("อิ").contains(&'ย'));
+        assert!(finals("อัว").contains(&'ย') && !finals("อัว").contains(&'ว'));
+        assert!(finals("อา").contains(&'น'));
+        assert!(finals("ไอ").is_empty());
+    }
+
+    #[test]
+    fn random_syllables_are_valid() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let initials: Vec<char> = "กขคงจฉชซดตถทนบปผพฟมยรลวสหอฮ".chars().collect();
+        let vowels: Vec<String> = VOWEL_FORMS.iter().map(|v| v.form.to_string()).collect();
+        for _ in 0..2000 {
+            let s = random_syllable(&mut rng, &initials, &vowels);
+            if let Some(f) = s.final_consonant {
+                assert!(s.vowel.allowed_finals().contains(&f), "{}", s.written());
+            }
+            if s.tone_mark.is_some() && s.final_consonant.is_some() {
+                assert!(!s.written().contains('็'), "{}", s.written());
+            }
+        }
+    }
+}
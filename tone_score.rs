@@ -0,0 +1,239 @@
+use crate::pitch::Contour;
+use crate::thai::{consonant_class, tone, vowel_form, Syllable, Tone, TONES};
+
+/// Contours are resampled to this many points, so durations don't matter.
+const POINTS: usize = 30;
+/// Shorter voiced parts are too short to have a tone.
+const MIN_VOICED: usize = 8;
+/// A pause this many frames long ends the first syllable.
+const SYLLABLE_GAP: usize = 10;
+
+/// The tone of the name a letter is called by: a consonant said on ออ, as
+/// in กอ, or a vowel said on อ.
+pub fn name_tone(letter: &str) -> Option<Tone> {
+    let mut chars = letter.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if let Some(class) = consonant_class(c) {
+            return Some(tone(class, true, true, None));
+        }
+    }
+    vowel_form(letter).map(|vowel| {
+        Syllable {
+            initial: 'อ',
+            vowel,
+            final_consonant: None,
+            tone_mark: None,
+        }
+        .tone()
+    })
+}
+
+/// The first syllable of a contour: voiced frames up to the first long
+/// pause. Letter names like "ก ไก่" go on with an example word whose tone
+/// is another.
+fn first_syllable(contour: &Contour) -> Contour {
+    let start = match contour.iter().position(|p| p.is_some()) {
+        Some(start) => start,
+        None => return Vec::new(),
+    };
+    let mut end = start;
+    let mut silent = 0;
+    for (i, pitch) in contour.iter().enumerate().skip(start) {
+        if pitch.is_some() {
+            end = i + 1;
+            silent = 0;
+        } else {
+            silent += 1;
+            if silent >= SYLLABLE_GAP {
+                break;
+            }
+        }
+    }
+    contour[start..end].to_vec()
+}
+
+/// The voiced pitch of a contour in semitones around its own average, which
+/// takes the speaker's range out, resampled to `POINTS` points.
+pub fn shape(contour: &Contour) -> Option<Vec<f32>> {
+    let semitones: Vec<f32> = contour
+        .iter()
+        .flatten()
+        .map(|f0| 12.0 * f0.log2())
+        .collect();
+    if semitones.len() < MIN_VOICED {
+        return None;
+    }
+    let mean = semitones.iter().sum::<f32>() / semitones.len() as f32;
+    let last = (semitones.len() - 1) as f32;
+    Some(
+        (0..POINTS)
+            .map(|i| {
+                let at = i as f32 * last / (POINTS - 1) as f32;
+                let (low, high) = (at.floor() as usize, at.ceil() as usize);
+                let part = at - low as f32;
+                semitones[low] * (1.0 - part) + semitones[high] * part - mean
+            })
+            .collect(),
+    )
+}
+
+/// The textbook shape of a tone in semitones, from its start to its end.
+fn template(tone: Tone) -> Vec<f32> {
+    let shape: Vec<f32> = (0..POINTS)
+        .map(|i| {
+            let t = i as f32 / (POINTS - 1) as f32;
+            match tone {
+                Tone::Mid => -t,
+                Tone::Low => -4.0 * t,
+                Tone::Falling => 3.0 - 12.0 * (t - 0.3).powi(2),
+                Tone::High => 5.0 * t * t,
+                Tone::Rising => 12.0 * (t - 0.4).powi(2),
+            }
+        })
+        .collect();
+    let mean = shape.iter().sum::<f32>() / POINTS as f32;
+    shape.iter().map(|p| p - mean).collect()
+}
+
+/// Dynamic time warping distance: the average semitones apart along the
+/// best alignment of the two shapes, allowing parts to be said faster or
+/// slower.
+pub fn dtw(a: &[f32], b: &[f32]) -> f32 {
+    let mut cost = vec![vec![f32::INFINITY; b.len() + 1]; a.len() + 1];
+    let mut steps = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    cost[0][0] = 0.0;
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let (best, best_steps) = [
+                (cost[i - 1][j - 1], steps[i - 1][j - 1]),
+                (cost[i - 1][j], steps[i - 1][j]),
+                (cost[i][j - 1], steps[i][j - 1]),
+            ]
+            .into_iter()
+            .fold(
+                (f32::INFINITY, 0),
+                |best, c| if c.0 < best.0 { c } else { best },
+            );
+            cost[i][j] = best + (a[i - 1] - b[j - 1]).abs();
+            steps[i][j] = best_steps + 1;
+        }
+    }
+    cost[a.len()][b.len()] / steps[a.len()][b.len()].max(1) as f32
+}
+
+/// The tone whose textbook shape is closest.
+pub fn classify(shape: &[f32]) -> Tone {
+    TONES
+        .iter()
+        .map(|tone| (*tone, dtw(shape, &template(*tone))))
+        .fold((Tone::Mid, f32::INFINITY), |best, t| {
+            if t.1 < best.1 {
+                t
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+/// Feedback on the first syllable of the learner's recording: the tone
+/// heard against the one expected, which is `tone` if known and otherwise
+/// the reference's.
+pub fn report(reference: &Contour, own: &Contour, tone: Option<Tone>) -> String {
+    let reference = shape(&first_syllable(reference));
+    let expected = tone.or(reference.as_deref().map(classify));
+    let own = match shape(&first_syllable(own)) {
+        Some(own) => own,
+        None if own.is_empty() => return String::from("Record yourself to get your tone scored"),
+        None => return String::from("Not enough voice in your recording to hear a tone"),
+    };
+    let heard = classify(&own);
+
+    let mut txt = match expected {
+        Some(expected) if expected == heard => {
+            format!("Your tone shape matches: {}", heard)
+        }
+        Some(expected) => format!("Heard a {} tone, expected {}", heard, expected),
+        None => format!("Heard a {} tone", heard),
+    };
+    if let Some(reference) = reference {
+        txt.push_str(&format!(
+            " ({:.1} semitones from the reference on average)",
+            dtw(&own, &reference)
+        ));
+    }
+    txt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A voiced contour with the textbook shape of `tone` around `base` Hz,
+    /// after some silence.
+    fn contour_of(tone: Tone, base: f32) -> Contour {
+        let mut contour = vec![None; 5];
+        contour.extend(
+            template(tone)
+                .iter()
+                .map(|st| Some(base * 2f32.powf(st / 12.0))),
+        );
+        contour
+    }
+
+    #[test]
+    fn dtw_distances() {
+        let a = [0.0, 1.0, 2.0, 1.0];
+        assert_eq!(dtw(&a, &a), 0.0);
+        assert_eq!(dtw(&[0.0, 0.0, 0.0], &[1.0, 1.0, 1.0]), 1.0);
+        // Saying the rise more slowly costs nothing.
+        assert_eq!(dtw(&a, &[0.0, 0.0, 1.0, 2.0, 2.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn classifies_each_tone_for_any_voice() {
+        for tone in TONES {
+            assert_eq!(classify(&template(tone)), tone);
+            for base in [110.0, 220.0] {
+                let shape = shape(&contour_of(tone, base)).unwrap();
+                assert_eq!(classify(&shape), tone, "{} at {} Hz", tone, base);
+            }
+        }
+    }
+
+    #[test]
+    fn first_syllable_ends_at_a_pause() {
+        let mut contour = contour_of(Tone::Rising, 150.0);
+        contour.extend(vec![None; SYLLABLE_GAP]);
+        contour.extend(contour_of(Tone::Low, 150.0));
+        let first = first_syllable(&contour);
+        assert_eq!(first.len(), POINTS);
+        assert_eq!(classify(&shape(&first).unwrap()), Tone::Rising);
+        // A short break is not a pause.
+        let mut contour = contour_of(Tone::High, 150.0);
+        contour.insert(15, None);
+        assert_eq!(first_syllable(&contour).len(), POINTS + 1);
+    }
+
+    #[test]
+    fn letter_name_tones() {
+        assert_eq!(name_tone("ก"), Some(Tone::Mid));
+        assert_eq!(name_tone("ข"), Some(Tone::Rising));
+        assert_eq!(name_tone("ค"), Some(Tone::Mid));
+        assert_eq!(name_tone("อา"), Some(Tone::Mid));
+        assert_eq!(name_tone("อะ"), Some(Tone::Low));
+        assert_eq!(name_tone("ไก่"), None);
+    }
+
+    #[test]
+    fn report_against_expected_tone() {
+        let reference = contour_of(Tone::Falling, 200.0);
+        let own = contour_of(Tone::Falling, 120.0);
+        assert!(report(&reference, &own, None).starts_with("Your tone shape matches: "));
+        assert!(report(&reference, &own, Some(Tone::High)).starts_with("Heard a "));
+        assert_eq!(
+            report(&reference, &Vec::new(), None),
+            "Record yourself to get your tone scored"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::syllables::syllable_parts;
+use crate::thai::{random_syllable, Syllable, TONES};
+use crate::{Context, Letter};
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, Button, Label};
+use std::sync::{Arc, Mutex};
+
+struct ToneDrill {
+    initials: Vec<char>,
+    vowels: Vec<String>,
+    syllable: Syllable,
+    answered: bool,
+    correct: usize,
+    total: usize,
+}
+
+impl ToneDrill {
+    fn new(letters: &[Letter], unlocked: &[usize]) -> ToneDrill {
+        let (initials, vowels) = syllable_parts(letters, unlocked);
+        let syllable = random_syllable(&mut rand::thread_rng(), &initials, &vowels);
+
+        ToneDrill {
+            initials,
+            vowels,
+            syllable,
+            answered: false,
+            correct: 0,
+            total: 0,
+        }
+    }
+
+    fn next(&mut self) {
+        self.syllable = random_syllable(&mut rand::thread_rng(), &self.initials, &self.vowels);
+        self.answered = false;
+    }
+}
+
+fn show_syllable(drill: &ToneDrill, label_syllable: &Label, label_feedback: &Label) {
+    label_syllable.set_markup(&format!(
+        "<span font_desc='Noto Looped Thai UI Normal 60'>{}</span>",
+        drill.syllable.written()
+    ));
+    label_feedback.set_text("Which tone does this syllable have?");
+}
+
+pub fn build_tone_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Tone drill")
+        .default_width(600)
+        .default_height(450)
+        .build();
+
+    let label_syllable = Label::new(Some(""));
+    let label_feedback = Label::new(Some(""));
+    let label_reasoning = Label::new(Some(""));
+    let label_score = Label::new(Some(""));
+    let button_next = Button::with_label("Next");
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    let h_box_tones = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+
+    let binding = letters.lock().unwrap();
+    let unlocked = shared_state.lock().unwrap().unlock.unlocked(&binding);
+    let drill = Arc::new(Mutex::new(ToneDrill::new(&binding, &unlocked)));
+    drop(binding);
+    show_syllable(&drill.lock().unwrap(), &label_syllable, &label_feedback);
+
+    for tone in TONES {
+        let button = Button::with_label(&tone.to_string());
+
+        let drill_1 = drill.clone();
+        let label_feedback_1 = label_feedback.clone();
+        let label_reasoning_1 = label_reasoning.clone();
+        let label_score_1 = label_score.clone();
+        button.connect_clicked(move |_| {
+            let mut d = drill_1.lock().unwrap();
+            if d.answered {
+                return;
+            }
+            d.answered = true;
+            d.total += 1;
+
+            let expected = d.syllable.tone();
+            if tone == expected {
+                d.correct += 1;
+                label_feedback_1.set_text(&format!("Correct: {} tone", expected));
+            } else {
+                label_feedback_1.set_text(&format!("Wrong: {} tone, not {}", expected, tone));
+            }
+            label_reasoning_1.set_text(&d.syllable.reasoning().join("\n"));
+            label_reasoning_1.show();
+            label_score_1.set_text(&format!("Score: {} / {}", d.correct, d.total));
+        });
+
+        h_box_tones.pack_start(&button, true, false, 0);
+    }
+
+    v_box.pack_start(&label_syllable, false, false, 0);
+    v_box.pack_start(&h_box_tones, false, false, 0);
+    v_box.pack_start(&label_feedback, false, false, 0);
+    v_box.pack_start(&label_reasoning, false, false, 0);
+    v_box.pack_start(&label_score, false, false, 0);
+    v_box.pack_start(&button_next, false, false, 0);
+
+    let label_reasoning_2 = label_reasoning.clone();
+    button_next.connect_clicked(move |_| {
+        let mut d = drill.lock().unwrap();
+        d.next();
+        show_syllable(&d, &label_syllable, &label_feedback);
+        label_reasoning_2.hide();
+    });
+
+    window.add(&v_box);
+    window.show_all();
+    label_reasoning.hide();
+
+    window
+}
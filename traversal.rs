@@ -0,0 +1,101 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// Visits every entry exactly once per round in a random order. The order
+/// depends only on the seed, so a session can be replayed by reusing it.
+#[derive(Clone)]
+pub struct ShuffledCycle {
+    seed: u64,
+    rng: StdRng,
+    indices: Vec<usize>,
+    order: Vec<usize>,
+    pos: usize,
+    round: usize,
+}
+
+impl ShuffledCycle {
+    pub fn new(seed: u64, indices: &[usize]) -> ShuffledCycle {
+        let mut cycle = ShuffledCycle {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            indices: indices.to_vec(),
+            order: Vec::new(),
+            pos: 0,
+            round: 0,
+        };
+        cycle.start_round();
+        cycle
+    }
+
+    pub fn random_seed() -> u64 {
+        rand::random::<u32>() as u64
+    }
+
+    fn start_round(&mut self) {
+        self.order = self.indices.clone();
+        self.order.shuffle(&mut self.rng);
+        self.pos = 0;
+        self.round += 1;
+    }
+
+    pub fn next(&mut self) -> Option<usize> {
+        if self.indices.is_empty() {
+            return None;
+        }
+        if self.pos == self.order.len() {
+            self.start_round();
+        }
+        self.pos += 1;
+        Some(self.order[self.pos - 1])
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Round number and how many entries of it have been shown.
+    pub fn progress(&self) -> String {
+        format!("Round {}: {} / {}", self.round, self.pos, self.order.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(cycle: &mut ShuffledCycle, n: usize) -> Vec<usize> {
+        (0..n).map(|_| cycle.next().unwrap()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_order() {
+        let indices: Vec<usize> = (0..20).collect();
+        let a = draw(&mut ShuffledCycle::new(42, &indices), 50);
+        let b = draw(&mut ShuffledCycle::new(42, &indices), 50);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn each_round_shows_every_entry_once() {
+        let indices = vec![3, 5, 8, 13, 21];
+        let mut cycle = ShuffledCycle::new(1, &indices);
+        for round in 1..=3 {
+            let mut shown = draw(&mut cycle, indices.len());
+            assert_eq!(cycle.progress(), format!("Round {}: 5 / 5", round));
+            shown.sort();
+            assert_eq!(shown, indices);
+        }
+        cycle.next();
+        assert_eq!(cycle.progress(), "Round 4: 1 / 5");
+    }
+
+    #[test]
+    fn empty_deck() {
+        assert_eq!(ShuffledCycle::new(1, &[]).next(), None);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::keyboard::Keyboard;
+use crate::{Context, Letter};
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, ComboBoxText, Entry, Label};
+use pango::glib::{markup_escape_text, random_int_range};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Drill {
+    Consonants,
+    Vowels,
+    Words,
+}
+
+impl Drill {
+    fn from_id(id: &str) -> Drill {
+        match id {
+            "vowels" => Drill::Vowels,
+            "words" => Drill::Words,
+            _ => Drill::Consonants,
+        }
+    }
+
+    /// What to type, from the `unlocked` letters.
+    fn items(&self, letters: &[Letter], unlocked: &[usize]) -> Vec<String> {
+        unlocked
+            .iter()
+            .map(|i| &letters[*i])
+            .filter_map(|l| match self {
+                Drill::Consonants if l.consonant => Some(l.letter.clone()),
+                Drill::Vowels if !l.consonant => Some(l.letter.clone()),
+                Drill::Words => l.example_word().map(String::from),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+struct Typing {
+    drill: Drill,
+    items: Vec<String>,
+    target: String,
+    typed: usize,
+    correct: usize,
+    errors: usize,
+    item_errors: usize,
+    first_wrong: Option<String>,
+    completed: usize,
+    started: Option<Instant>,
+    elapsed: Duration,
+}
+
+impl Typing {
+    fn new(drill: Drill, letters: &[Letter], unlocked: &[usize]) -> Typing {
+        let mut t = Typing {
+            drill,
+            items: drill.items(letters, unlocked),
+            target: String::new(),
+            typed: 0,
+            correct: 0,
+            errors: 0,
+            item_errors: 0,
+            first_wrong: None,
+            completed: 0,
+            started: None,
+            elapsed: Duration::ZERO,
+        };
+        t.next();
+        t
+    }
+
+    fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let r = random_int_range(0, self.items.len() as i32);
+        self.target = self.items[r as usize].clone();
+        self.typed = 0;
+        self.item_errors = 0;
+        self.first_wrong = None;
+        if let Some(started) = self.started.take() {
+            self.elapsed += started.elapsed();
+        }
+    }
+
+    fn remaining(&self) -> String {
+        self.target.chars().skip(self.typed).collect()
+    }
+
+    fn accuracy(&self) -> f64 {
+        let total = self.correct + self.errors;
+        if total == 0 {
+            100.0
+        } else {
+            self.correct as f64 * 100.0 / total as f64
+        }
+    }
+
+    /// Correctly typed characters per minute.
+    fn speed(&self) -> f64 {
+        let elapsed = self.elapsed + self.started.map_or(Duration::ZERO, |s| s.elapsed());
+        if elapsed.as_secs_f64() < 1.0 {
+            0.0
+        } else {
+            self.correct as f64 * 60.0 / elapsed.as_secs_f64()
+        }
+    }
+
+    fn stats(&self) -> String {
+        format!(
+            "Completed: {}   Accuracy: {:.0}%   Speed: {:.0} chars/min   Errors: {}",
+            self.completed,
+            self.accuracy(),
+            self.speed(),
+            self.errors
+        )
+    }
+}
+
+fn show_target(typing: &Typing, label_target: &Label, keyboard: &Keyboard) {
+    if typing.items.is_empty() {
+        label_target.set_text("Nothing to type in this drill for these letters");
+        return;
+    }
+    label_target.set_markup(&format!(
+        "<span font_desc='Noto Looped Thai UI Normal 40'>{}</span>",
+        markup_escape_text(&typing.target)
+    ));
+    keyboard.highlight(&typing.remaining().chars().take(1).collect::<String>());
+}
+
+/// A drill over the letters unlocked so far.
+fn new_typing(drill: Drill, letters: &Mutex<Vec<Letter>>, shared_state: &Mutex<Context>) -> Typing {
+    let binding = letters.lock().unwrap();
+    let unlocked = shared_state.lock().unwrap().unlock.unlocked(&binding);
+    Typing::new(drill, &binding, &unlocked)
+}
+
+pub fn build_typing_window(
+    app: &Application,
+    letters: Arc<Mutex<Vec<Letter>>>,
+    shared_state: Arc<Mutex<Context>>,
+) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Typing drill")
+        .default_width(700)
+        .default_height(500)
+        .build();
+
+    let combo_drill = ComboBoxText::new();
+    combo_drill.append(Some("consonants"), "Consonants");
+    combo_drill.append(Some("vowels"), "Vowels");
+    combo_drill.append(Some("words"), "Example words");
+    combo_drill.set_active_id(Some("consonants"));
+
+    let label_target = Label::new(Some(""));
+    let label_stats = Label::new(Some(""));
+    let entry = Entry::new();
+    let keyboard = Keyboard::new();
+
+    let v_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    v_box.pack_start(&combo_drill, false, false, 0);
+    v_box.pack_start(&label_target, false, false, 0);
+    v_box.pack_start(&entry, false, false, 0);
+    v_box.pack_start(&label_stats, false, false, 0);
+    v_box.pack_start(&keyboard.widget, false, false, 0);
+
+    let typing = Arc::new(Mutex::new(new_typing(
+        Drill::Consonants,
+        &letters,
+        &shared_state,
+    )));
+    show_target(&typing.lock().unwrap(), &label_target, &keyboard);
+    label_stats.set_text(&typing.lock().unwrap().stats());
+    entry.set_sensitive(!typing.lock().unwrap().items.is_empty());
+
+    let typing_1 = typing.clone();
+    let label_target_1 = label_target.clone();
+    let label_stats_1 = label_stats.clone();
+    let keyboard_1 = keyboard.clone();
+    let entry_1 = entry.clone();
+    let shared_state_1 = shared_state.clone();
+    combo_drill.connect_changed(move |combo| {
+        let drill = Drill::from_id(&combo.active_id().unwrap());
+        *typing_1.lock().unwrap() = new_typing(drill, &letters, &shared_state_1);
+        entry_1.set_text("");
+        entry_1.set_sensitive(!typing_1.lock().unwrap().items.is_empty());
+        show_target(&typing_1.lock().unwrap(), &label_target_1, &keyboard_1);
+        label_stats_1.set_text(&typing_1.lock().unwrap().stats());
+    });
+
+    let typing_2 = typing.clone();
+    let keyboard_2 = keyboard.clone();
+    let label_stats_2 = label_stats.clone();
+    entry.connect_changed(move |entry| {
+        let text = entry.text().to_string();
+        let mut t = typing_2.lock().unwrap();
+        let expected: String = t.target.chars().take(text.chars().count()).collect();
+
+        if text == expected {
+            let len = text.chars().count();
+            if len > t.typed {
+                if t.started.is_none() {
+                    t.started = Some(Instant::now());
+                }
+                t.correct += len - t.typed;
+            }
+            t.typed = len;
+            if text == t.target {
+                // Only single consonants give a meaningful confusion pair.
+                let given = match t.drill {
+                    Drill::Consonants => t.first_wrong.clone(),
+                    _ => None,
+                };
+                let mut state = shared_state.lock().unwrap();
+                state.answer(&t.target, given.as_deref(), t.item_errors == 0);
+                state.goals.study();
+                drop(state);
+                t.completed += 1;
+                t.next();
+                show_target(&t, &label_target, &keyboard_2);
+                label_stats_2.set_text(&t.stats());
+                drop(t);
+                entry.set_text("");
+                return;
+            }
+            keyboard_2.highlight(&t.remaining().chars().take(1).collect::<String>());
+            label_stats_2.set_text(&t.stats());
+        } else {
+            // Reject the wrong character and keep what was typed correctly so far.
+            t.errors += 1;
+            t.item_errors += 1;
+            if t.first_wrong.is_none() {
+                t.first_wrong = Some(text.chars().skip(t.typed).collect());
+            }
+            label_stats_2.set_text(&t.stats());
+            let correct: String = t.target.chars().take(t.typed).collect();
+            drop(t);
+            entry.set_text(&correct);
+            entry.set_position(-1);
+        }
+    });
+
+    let entry_2 = entry.clone();
+    keyboard.connect_key(move |c| {
+        let mut text = entry_2.text().to_string();
+        text.push(c);
+        entry_2.set_text(&text);
+        entry_2.set_position(-1);
+    });
+
+    window.add(&v_box);
+    window.show_all();
+    entry.grab_focus();
+
+    window
+}
@@ -0,0 +1,201 @@
+use crate::events::{self, Event};
+use crate::storage;
+use crate::thai::vowel_form;
+use crate::{Context, Letter};
+use gtk::prelude::*;
+use gtk::{CheckButton, Label};
+use std::sync::{Arc, Mutex};
+
+const UNLOCK_FILE: &str = "unlock.tsv";
+/// Accuracy is measured over this many recent answers.
+const WINDOW: usize = 20;
+/// Correct answers out of the window needed to unlock the next stage.
+const NEEDED: usize = 17;
+
+const STAGES: [&str; 5] = [
+    "Mid-class consonants and long vowels",
+    "High-class consonants",
+    "Common low-class consonants",
+    "Short vowels",
+    "Rare consonants",
+];
+
+/// The stage a deck entry is introduced in. Anything else, like a word,
+/// isn't part of any stage.
+fn stage_of(letter: &str) -> Option<usize> {
+    if let Some(vowel) = vowel_form(letter) {
+        return Some(if vowel.long { 0 } else { 3 });
+    }
+    let mut chars = letter.chars();
+    let c = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => return None,
+    };
+    if "กจดตบปอ".contains(c) {
+        Some(0)
+    } else if "ขฉถผฝสห".contains(c) {
+        Some(1)
+    } else if "คงชซทนพฟมยรลวฮ".contains(c) {
+        Some(2)
+    } else if ('ก'..='ฮ').contains(&c) {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+/// Which entries the learner has unlocked. New ones are added a stage at a
+/// time, once the recent answers on the unlocked ones are accurate enough.
+#[derive(Clone)]
+pub struct Unlock {
+    pub enabled: bool,
+    stage: usize,
+    recent: Vec<bool>,
+    /// Set when a stage was just unlocked, until the next answer.
+    news: bool,
+}
+
+impl Unlock {
+    pub fn load() -> Unlock {
+        let mut unlock = Unlock {
+            enabled: true,
+            stage: 0,
+            recent: Vec::new(),
+            news: false,
+        };
+        for record in storage::read_records(&storage::data_file(UNLOCK_FILE)) {
+            match record.as_slice() {
+                [kind, enabled] if kind == "enabled" => unlock.enabled = enabled == "1",
+                [kind, stage] if kind == "stage" => {
+                    unlock.stage = stage.parse().unwrap_or(0).min(STAGES.len() - 1);
+                }
+                [kind, recent] if kind == "recent" => {
+                    unlock.recent = recent.chars().map(|c| c == '1').collect();
+                }
+                _ => (),
+            }
+        }
+        unlock
+    }
+
+    pub fn save(&self) {
+        let recent: String = self
+            .recent
+            .iter()
+            .map(|r| if *r { '1' } else { '0' })
+            .collect();
+        let records = vec![
+            vec![
+                String::from("enabled"),
+                String::from(if self.enabled { "1" } else { "0" }),
+            ],
+            vec![String::from("stage"), self.stage.to_string()],
+            vec![String::from("recent"), recent],
+        ];
+        storage::write_records(&storage::data_file(UNLOCK_FILE), &records);
+    }
+
+    pub fn is_unlocked(&self, letter: &str) -> bool {
+        !self.enabled || stage_of(letter).is_none_or(|s| s <= self.stage)
+    }
+
+    /// Those of `indices` into `letters` that are unlocked, or all of them
+    /// when none is unlocked yet.
+    pub fn unlocked_of(&self, letters: &[Letter], indices: &[usize]) -> Vec<usize> {
+        let unlocked: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|i| self.is_unlocked(&letters[*i].letter))
+            .collect();
+        if unlocked.is_empty() {
+            indices.to_vec()
+        } else {
+            unlocked
+        }
+    }
+
+    /// The unlocked letters by index, for the drills that use all of them.
+    pub fn unlocked(&self, letters: &[Letter]) -> Vec<usize> {
+        self.unlocked_of(letters, &(0..letters.len()).collect::<Vec<usize>>())
+    }
+
+    /// Counts an answer about an unlocked entry towards the next stage.
+    pub fn record(&mut self, letter: &str, correct: bool) {
+        if !self.enabled || stage_of(letter).is_none_or(|s| s > self.stage) {
+            return;
+        }
+        self.news = false;
+        self.recent.push(correct);
+        if self.recent.len() > WINDOW {
+            self.recent.remove(0);
+        }
+
+        let right = self.recent.iter().filter(|r| **r).count();
+        if self.recent.len() == WINDOW && right >= NEEDED && self.stage < STAGES.len() - 1 {
+            self.stage += 1;
+            self.recent.clear();
+            self.news = true;
+        }
+        self.save();
+    }
+
+    pub fn status(&self, letters: &[String]) -> String {
+        if !self.enabled {
+            return String::from("All letters unlocked");
+        }
+        let unlocked = letters.iter().filter(|l| self.is_unlocked(l)).count();
+        let mut txt = format!(
+            "Stage {} of {}: {} ({} of {} letters)",
+            self.stage + 1,
+            STAGES.len(),
+            STAGES[self.stage],
+            unlocked,
+            letters.len()
+        );
+        if self.news {
+            txt = format!("New letters unlocked! {}", txt);
+        } else if self.stage < STAGES.len() - 1 {
+            let right = self.recent.iter().filter(|r| **r).count();
+            txt.push_str(&format!(
+                "\nRecent answers: {} of {} right; {} of the last {} unlocks the next stage",
+                right,
+                self.recent.len(),
+                NEEDED,
+                WINDOW
+            ));
+        }
+        txt
+    }
+}
+
+/// Shows the unlock stage and lets gradual unlocking be switched off.
+pub fn build_unlock_status(letters: &[Letter], shared_state: Arc<Mutex<Context>>) -> gtk::Box {
+    let letters: Vec<String> = letters.iter().map(|l| l.letter.clone()).collect();
+    let check_enabled = CheckButton::with_label("Introduce letters gradually");
+    check_enabled.set_active(shared_state.lock().unwrap().unlock.enabled);
+    let label_status = Label::new(None);
+
+    let h_box = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    h_box.pack_start(&check_enabled, false, false, 0);
+    h_box.pack_start(&label_status, true, false, 0);
+
+    let shared_state_1 = shared_state.clone();
+    let letters_1 = letters.clone();
+    let label_status_1 = label_status.clone();
+    check_enabled.connect_toggled(move |check| {
+        let mut state = shared_state_1.lock().unwrap();
+        state.unlock.enabled = check.is_active();
+        state.unlock.save();
+        label_status_1.set_text(&state.unlock.status(&letters_1));
+    });
+
+    // Answers given in the drill windows can unlock letters too.
+    label_status.set_text(&shared_state.lock().unwrap().unlock.status(&letters));
+    events::subscribe(move |event| {
+        if event == Event::Studied {
+            label_status.set_text(&shared_state.lock().unwrap().unlock.status(&letters));
+        }
+    });
+
+    h_box
+}